@@ -0,0 +1,223 @@
+// Run with `cargo bench`; compare against a stored run with
+// `cargo bench -- --save-baseline before` and then `cargo bench -- --baseline before`.
+// Puzzles whose input file is missing from `in/` are skipped.
+//
+// `end-to-end` times each registered puzzle from its input file, `parse` times reading
+// each day's input, and `solve` times each part on input parsed beforehand.
+// Days 12, 14 and 18 act on each line as they read it, so there is no parsed value to time
+// a solver on and their end-to-end time stands in for solving; day 12's parse bench times
+// decoding its instructions alone.
+
+use std::fmt::Debug;
+use criterion::{criterion_group, criterion_main, Criterion, BenchmarkGroup, black_box};
+use criterion::measurement::WallTime;
+use code_advent_2020::puzzle::PuzzleRegistry;
+use code_advent_2020::input::{self, personal_input, numbered_lines};
+use code_advent_2020::{day1, day2, day3, day4, day5, day6, day7, day8, day9, day10, day11, day12, day13,
+                       day15, day17, day20, day21, day23, day24, day25};
+use code_advent_2020::day1::Reuse;
+use code_advent_2020::day2::{CountInRange, ExactlyOnePosition};
+use code_advent_2020::day3::{SlopeMap, Slope};
+use code_advent_2020::day4::Schema;
+use code_advent_2020::day5::{SeatMap, PassCodec};
+use code_advent_2020::day6::Aggregate;
+use code_advent_2020::day11::GameOfSeats;
+use code_advent_2020::day16::Notes;
+use code_advent_2020::day19::Rules;
+use code_advent_2020::day20::PuzzlePieces;
+use code_advent_2020::day21::Allergens;
+use code_advent_2020::day22::Game;
+use code_advent_2020::day8::CPUState;
+use code_advent_2020::day15::ElfGame;
+use code_advent_2020::day17::ConwayCubes;
+use code_advent_2020::day23::CupRing;
+
+fn end_to_end(c: &mut Criterion) {
+    let registry = PuzzleRegistry::all();
+    let mut group = c.benchmark_group("end-to-end");
+    group.sample_size(10);
+    for puzzle in registry.iter() {
+        if let Some(filename) = personal_input(puzzle.day()) {
+            group.bench_function(puzzle.key(), |b| b.iter(|| puzzle.solve(black_box(filename.as_str()))));
+        }
+    }
+    group.finish();
+}
+
+fn bench_parse<T, F: Fn(&str) -> T>(group: &mut BenchmarkGroup<WallTime>, day: usize, parse: F) {
+    if let Some(filename) = personal_input(day) {
+        group.bench_function(day.to_string(), |b| b.iter(|| parse(black_box(filename.as_str()))));
+    }
+}
+
+fn each_line<T, F: Fn(&str) -> T>(filename: &str, parse: F) -> Vec<T> {
+    numbered_lines(filename).unwrap().map(|(_, line)| parse(line.as_str())).collect()
+}
+
+fn parse(c: &mut Criterion) {
+    let mut group = c.benchmark_group("parse");
+    bench_parse(&mut group, 1, |f| input::numbers(f, 1));
+    bench_parse(&mut group, 2, day2::policy_lines);
+    bench_parse(&mut group, 3, SlopeMap::from_file);
+    bench_parse(&mut group, 4, |f| day4::parse_passports(f, &Schema::default()));
+    bench_parse(&mut group, 5, |f| SeatMap::from_file(f, PassCodec::default()));
+    bench_parse(&mut group, 6, day6::groups);
+    bench_parse(&mut group, 7, day7::create_graph_from);
+    bench_parse(&mut group, 8, CPUState::from_file);
+    bench_parse(&mut group, 9, |f| input::numbers(f, 9));
+    bench_parse(&mut group, 10, day10::make_joltage_vec);
+    bench_parse(&mut group, 11, GameOfSeats::from);
+    bench_parse(&mut group, 12, |f| each_line(f, day12::decode_line));
+    bench_parse(&mut group, 13, |f| (day13::puzzle_1_inputs(f), day13::puzzle_2_inputs(f)));
+    bench_parse(&mut group, 15, day15::starting_nums);
+    bench_parse(&mut group, 16, |f| Notes::from(f, true));
+    bench_parse(&mut group, 17, |f| ConwayCubes::from(f, 3));
+    bench_parse(&mut group, 19, |f| Rules::from_file(f, false));
+    bench_parse(&mut group, 20, PuzzlePieces::from);
+    bench_parse(&mut group, 21, day21::foods);
+    bench_parse(&mut group, 22, |f| Game::from(f, false));
+    bench_parse(&mut group, 23, day23::starting_cups);
+    bench_parse(&mut group, 24, day24::tile_paths);
+    bench_parse(&mut group, 25, day25::public_keys);
+    group.finish();
+}
+
+fn parsed<T, E: Debug, F: Fn(&str) -> Result<T,E>>(day: usize, parse: F) -> Option<T> {
+    personal_input(day).map(|filename| parse(filename.as_str()).unwrap())
+}
+
+fn bench_solve<T, R, F: Fn(&T) -> R>(group: &mut BenchmarkGroup<WallTime>, key: &str, input: &T, solve: F) {
+    group.bench_function(key, |b| b.iter(|| solve(black_box(input))));
+}
+
+fn solve(c: &mut Criterion) {
+    let mut group = c.benchmark_group("solve");
+    group.sample_size(10);
+    if let Some(nums) = parsed(1, |f| input::numbers(f, 1)) {
+        bench_solve(&mut group, "1_1", &nums, |nums| day1::find_k_sum(nums, 2, 2020, Reuse::Forbidden));
+        bench_solve(&mut group, "1_2", &nums, |nums| day1::find_k_sum(nums, 3, 2020, Reuse::Forbidden));
+    }
+    if let Some(lines) = parsed(2, day2::policy_lines) {
+        bench_solve(&mut group, "2_1", &lines, |lines| day2::count_valid_lines(lines, &CountInRange));
+        bench_solve(&mut group, "2_2", &lines, |lines| day2::count_valid_lines(lines, &ExactlyOnePosition));
+    }
+    if let Some(map) = parsed(3, SlopeMap::from_file) {
+        bench_solve(&mut group, "3_1", &map, |map| map.trees_on(Slope::new(3, 1).unwrap()));
+        bench_solve(&mut group, "3_2", &map, day3::slope_product);
+    }
+    let schema = Schema::default();
+    if let Some(passports) = parsed(4, |f| day4::parse_passports(f, &schema)) {
+        bench_solve(&mut group, "4_1", &passports, |p| day4::count_matching(p, &schema, |s, fields| s.has_required(fields)));
+        bench_solve(&mut group, "4_2", &passports, |p| day4::count_matching(p, &schema, |s, fields| s.accepts(fields)));
+    }
+    if let Some(ids) = parsed(5, day5::seat_ids) {
+        bench_solve(&mut group, "5_1", &ids, |ids| ids.iter().max().copied());
+    }
+    if let Some(map) = parsed(5, |f| SeatMap::from_file(f, PassCodec::default())) {
+        bench_solve(&mut group, "5_2", &map, |map| map.single_gaps());
+    }
+    if let Some(groups) = parsed(6, day6::groups) {
+        bench_solve(&mut group, "6_1", &groups, |groups| day6::total_of(groups, Aggregate::Union));
+        bench_solve(&mut group, "6_2", &groups, |groups| day6::total_of(groups, Aggregate::Intersection));
+    }
+    if let Some(graph) = parsed(7, day7::create_graph_from) {
+        bench_solve(&mut group, "7_1", &graph, |graph| graph.all_predecessors_of("shiny gold"));
+        bench_solve(&mut group, "7_2", &graph, |graph| graph.bags_within("shiny gold"));
+    }
+    if let Some(program) = parsed(8, CPUState::from_file) {
+        bench_solve(&mut group, "8_1", &program, |program| day8::terminates(program.clone()));
+        bench_solve(&mut group, "8_2", &program, day8::repair);
+    }
+    if let Some(nums) = parsed(9, |f| input::numbers(f, 9)) {
+        bench_solve(&mut group, "9_1", &nums, |nums| day9::find_failing_xmas_num(nums, 25));
+        bench_solve(&mut group, "9_2", &nums, |nums| day9::find_encryption_weakness(nums, 25));
+    }
+    if let Some(nums) = parsed(10, day10::make_joltage_vec) {
+        bench_solve(&mut group, "10_1", &nums, |nums| day10::count_jolt_jumps(nums));
+        bench_solve(&mut group, "10_2", &nums, |nums| day10::count_arrangements(nums));
+    }
+    if let Some(seats) = parsed(11, GameOfSeats::from) {
+        bench_solve(&mut group, "11_1", &seats, |seats| day11::num_occupied_at_stable(day11::puzzle_1_iter(seats.clone())));
+        bench_solve(&mut group, "11_2", &seats, |seats| day11::num_occupied_at_stable(day11::puzzle_2_iter(seats.clone())));
+    }
+    if let Some((departure, busses)) = parsed(13, day13::puzzle_1_inputs) {
+        bench_solve(&mut group, "13_1", &busses, |busses| day13::best_bus_and_wait(busses, departure));
+    }
+    if let Some(offsets) = parsed(13, day13::puzzle_2_inputs) {
+        bench_solve(&mut group, "13_2", &offsets, |offsets| day13::earliest_alignment(offsets));
+    }
+    if let Some(nums) = parsed(15, day15::starting_nums) {
+        bench_solve(&mut group, "15_1", &nums, |nums| ElfGame::elf(nums, 2020));
+        bench_solve(&mut group, "15_2", &nums, |nums| ElfGame::elf(nums, 30000000));
+    }
+    if let Some(notes) = parsed(16, |f| Notes::from(f, true)) {
+        bench_solve(&mut group, "16_1", &notes, |notes| notes.nearby_ticket_scanning_error_rate());
+    }
+    if let Some(notes) = parsed(16, |f| Notes::from(f, false)) {
+        bench_solve(&mut group, "16_2", &notes, |notes| notes.departure_product());
+    }
+    for (part, dimension) in [(1, 3), (2, 4)].iter() {
+        if let Some(cubes) = parsed(17, |f| ConwayCubes::from(f, *dimension)) {
+            bench_solve(&mut group, format!("17_{}", part).as_str(), &cubes, |cubes| day17::after_n_cycles(cubes.clone(), 6));
+        }
+    }
+    for (part, looping) in [(1, false), (2, true)].iter() {
+        if let Some((rules, messages)) = parsed(19, |f| Rules::from_file(f, *looping)) {
+            bench_solve(&mut group, format!("19_{}", part).as_str(), &messages, |messages| rules.count_matches(messages));
+        }
+    }
+    if let Some(pieces) = parsed(20, PuzzlePieces::from) {
+        bench_solve(&mut group, "20_1", &pieces, |pieces| pieces.corner_product());
+        bench_solve(&mut group, "20_2", &pieces, day20::find_monsters);
+    }
+    if let Some(foods) = parsed(21, day21::foods) {
+        bench_solve(&mut group, "21_1", &foods, |foods| Allergens::from_foods(foods).map(|a| a.allergen_free_counts()));
+        bench_solve(&mut group, "21_2", &foods, |foods| Allergens::from_foods(foods).map(|a| a.canonical_dangerous_list()));
+    }
+    for (part, recursive) in [(1, false), (2, true)].iter() {
+        if let Some(game) = parsed(22, |f| Game::from(f, *recursive)) {
+            bench_solve(&mut group, format!("22_{}", part).as_str(), &game, |game| game.clone().winning_score());
+        }
+    }
+    if let Some(cups) = parsed(23, day23::starting_cups) {
+        bench_solve(&mut group, "23_1", &cups, |cups| day23::play_1(cups));
+        bench_solve(&mut group, "23_2", &cups, |cups| day23::play_2(cups));
+    }
+    if let Some(paths) = parsed(24, day24::tile_paths) {
+        bench_solve(&mut group, "24_1", &paths, |paths| day24::black_tiles(paths, 0));
+        bench_solve(&mut group, "24_2", &paths, |paths| day24::black_tiles(paths, 100));
+    }
+    if let Some((card, door)) = parsed(25, day25::public_keys) {
+        bench_solve(&mut group, "25_1", &(card, door), |(card, door)| day25::find_encryption_key(*card, *door));
+    }
+    group.finish();
+}
+
+fn phases(c: &mut Criterion) {
+    let mut group = c.benchmark_group("phase");
+    group.sample_size(10);
+    if let Some(filename) = personal_input(8) {
+        let program = CPUState::from_file(filename.as_str()).unwrap();
+        group.bench_function("8 terminates", |b| b.iter(|| day8::terminates(black_box(program.clone()))));
+    }
+    if let Some(filename) = personal_input(15) {
+        let nums = day15::starting_nums(filename.as_str()).unwrap();
+        group.bench_function("15 ElfGame::elf", |b| b.iter(|| ElfGame::elf(black_box(&nums), 100_000)));
+    }
+    if let Some(filename) = personal_input(17) {
+        for dimension in 3..=4 {
+            let cubes = ConwayCubes::from(filename.as_str(), dimension).unwrap().cycle().cycle();
+            group.bench_function(format!("17 ConwayCubes::cycle {}d", dimension),
+                                 |b| b.iter(|| black_box(&cubes).cycle()));
+        }
+    }
+    if let Some(filename) = personal_input(23) {
+        let cups = day23::starting_cups(filename.as_str()).unwrap();
+        let mut ring = CupRing::new(&cups, 1_000_000);
+        group.bench_function("23 CupRing::move_once", |b| b.iter(|| ring.move_once()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, end_to_end, parse, solve, phases);
+criterion_main!(benches);
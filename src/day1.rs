@@ -1,44 +1,342 @@
-use std::io;
-use advent_code_lib::file2nums;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::puzzle::PuzzleRegistry;
+use crate::input::numbers;
+use crate::error::{AdventResult, AdventError};
 
-pub fn solve_1() -> io::Result<String> {
-    let nums = file2nums("in/day1.txt")?;
-    for i in 0..nums.len() {
-        for j in i..nums.len() {
-            if nums[i] + nums[j] == 2020 {
-                return Ok(format!("{} + {} == 2020; {} * {} == {}", nums[i], nums[j], nums[i], nums[j], nums[i] * nums[j]));
+const DAY: usize = 1;
+const TARGET: isize = 2020;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    solve(filename, 2)
+}
+
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    solve(filename, 3)
+}
+
+fn solve(filename: &str, k: usize) -> AdventResult<String> {
+    let nums = numbers(filename, DAY)?;
+    find_k_sum(&nums, k, TARGET, Reuse::Forbidden)
+        .map(|sum| sum.to_string())
+        .ok_or_else(|| AdventError::no_solution(DAY, format!("no {} entries sum to {}", k, TARGET).as_str()))
+}
+
+pub fn all_k_sums_in(filename: &str, k: usize, target: isize, reuse: Reuse) -> AdventResult<Vec<KSum>> {
+    let nums = numbers(filename, DAY)?;
+    Ok(all_k_sums(&nums, k, target, reuse).collect())
+}
+
+pub fn count_k_sums_in(filename: &str, k: usize, target: isize, reuse: Reuse) -> AdventResult<usize> {
+    Ok(count_k_sums(&numbers(filename, DAY)?, k, target, reuse))
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(1, 1, solve_1);
+    registry.add(1, 2, solve_2);
+}
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Reuse {
+    Allowed, Forbidden
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct KSum {
+    pub indices: Vec<usize>,
+    pub values: Vec<isize>
+}
+
+impl KSum {
+    fn new(nums: &[isize], mut indices: Vec<usize>) -> Self {
+        indices.sort();
+        KSum {values: indices.iter().map(|i| nums[*i]).collect(), indices}
+    }
+
+    pub fn sum(&self) -> isize {
+        self.values.iter().sum()
+    }
+
+    pub fn product(&self) -> isize {
+        self.values.iter().product()
+    }
+}
+
+impl Display for KSum {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let terms: Vec<String> = self.values.iter().map(|v| v.to_string()).collect();
+        write!(f, "{} == {}; {} == {}", terms.join(" + "), self.sum(), terms.join(" * "), self.product())
+    }
+}
+
+// Pairs are found in one pass with a hash lookup; other sizes sort the
+// values, fix all but two terms, and close the last two with two pointers.
+pub fn find_k_sum(nums: &[isize], k: usize, target: isize, reuse: Reuse) -> Option<KSum> {
+    let indices = match k {
+        0 => if target == 0 {Some(vec![])} else {None},
+        2 => find_pair(nums, target, reuse),
+        _ => {
+            let mut sorted: Vec<usize> = (0..nums.len()).collect();
+            sorted.sort_by_key(|i| nums[*i]);
+            let mut chosen = Vec::new();
+            if find_sorted(nums, &sorted, 0, k, target, reuse, &mut chosen) {Some(chosen)} else {None}
+        }
+    };
+    indices.map(|indices| KSum::new(nums, indices))
+}
+
+fn find_pair(nums: &[isize], target: isize, reuse: Reuse) -> Option<Vec<usize>> {
+    let mut seen = HashMap::new();
+    for (i, num) in nums.iter().enumerate() {
+        if let Some(j) = seen.get(&(target - num)) {
+            return Some(vec![*j, i]);
+        }
+        if reuse == Reuse::Allowed && 2 * num == target {
+            return Some(vec![i, i]);
+        }
+        seen.entry(*num).or_insert(i);
+    }
+    None
+}
+
+fn find_sorted(nums: &[isize], sorted: &[usize], start: usize, k: usize, target: isize, reuse: Reuse, chosen: &mut Vec<usize>) -> bool {
+    let next = |p: usize| if reuse == Reuse::Allowed {p} else {p + 1};
+    match k {
+        1 => match sorted[start.min(sorted.len())..].iter().find(|i| nums[**i] == target) {
+            Some(i) => {chosen.push(*i); true}
+            None => false
+        },
+        2 => {
+            if sorted.is_empty() {
+                return false;
+            }
+            let (mut lo, mut hi) = (start, sorted.len() - 1);
+            while lo < hi || reuse == Reuse::Allowed && lo == hi {
+                let sum = nums[sorted[lo]] + nums[sorted[hi]];
+                if sum == target {
+                    chosen.push(sorted[lo]);
+                    chosen.push(sorted[hi]);
+                    return true;
+                } else if sum < target {
+                    lo += 1;
+                } else if hi == 0 {
+                    return false;
+                } else {
+                    hi -= 1;
+                }
+            }
+            false
+        }
+        _ => {
+            for p in start..sorted.len() {
+                chosen.push(sorted[p]);
+                if find_sorted(nums, sorted, next(p), k - 1, target - nums[sorted[p]], reuse, chosen) {
+                    return true;
+                }
+                chosen.pop();
+            }
+            false
+        }
+    }
+}
+
+pub fn all_k_sums(nums: &[isize], k: usize, target: isize, reuse: Reuse) -> KSums {
+    KSums::new(nums, k, target, reuse)
+}
+
+// Counts combinations without building them: counts[j] maps each sum
+// reachable with j entries to the number of ways of reaching it.
+pub fn count_k_sums(nums: &[isize], k: usize, target: isize, reuse: Reuse) -> usize {
+    let mut counts: Vec<HashMap<isize,usize>> = vec![HashMap::new(); k + 1];
+    counts[0].insert(0, 1);
+    for num in nums.iter() {
+        let js: Vec<usize> = match reuse {
+            Reuse::Allowed => (1..=k).collect(),
+            Reuse::Forbidden => (1..=k).rev().collect()
+        };
+        for j in js {
+            let extended: Vec<(isize,usize)> = counts[j - 1].iter().map(|(s, c)| (s + num, *c)).collect();
+            for (sum, count) in extended {
+                *counts[j].entry(sum).or_insert(0) += count;
             }
         }
     }
-    Ok("Failed".to_string())
+    counts[k].get(&target).copied().unwrap_or(0)
 }
 
-pub fn solve_2() -> io::Result<String> {
-    let nums = file2nums("in/day1.txt")?;
-    for i in 0..nums.len() {
-        for j in i..nums.len() {
-            for k in j..nums.len() {
-                if nums[i] + nums[j] + nums[k] == 2020 {
-                    return Ok(format!("{} + {} + {} == 2020; {} * {} * {} == {}", nums[i], nums[j], nums[k], nums[i], nums[j], nums[k], nums[i] * nums[j] * nums[k]));
+// Walks every prefix of k - 1 entries in index order, like an odometer,
+// and looks up the entries that complete each prefix by value.
+pub struct KSums<'a> {
+    nums: &'a [isize],
+    target: isize,
+    reuse: Reuse,
+    by_value: HashMap<isize,Vec<usize>>,
+    prefix: Vec<usize>,
+    ready: Vec<KSum>,
+    done: bool
+}
+
+impl <'a> KSums<'a> {
+    fn new(nums: &'a [isize], k: usize, target: isize, reuse: Reuse) -> Self {
+        let mut by_value: HashMap<isize,Vec<usize>> = HashMap::new();
+        for (i, num) in nums.iter().enumerate() {
+            by_value.entry(*num).or_insert_with(Vec::new).push(i);
+        }
+        let mut sums = KSums {nums, target, reuse, by_value, prefix: Vec::new(), ready: Vec::new(), done: false};
+        if k == 0 {
+            if target == 0 {
+                sums.ready.push(KSum::new(nums, vec![]));
+            }
+            sums.done = true;
+        } else {
+            sums.prefix = (0..k - 1).map(|i| if reuse == Reuse::Allowed {0} else {i}).collect();
+            sums.done = match reuse {
+                Reuse::Allowed => nums.is_empty(),
+                Reuse::Forbidden => nums.len() < k
+            };
+        }
+        sums
+    }
+
+    fn max_at(&self, i: usize) -> usize {
+        match self.reuse {
+            Reuse::Allowed => self.nums.len() - 1,
+            Reuse::Forbidden => self.nums.len() + i - self.prefix.len() - 1
+        }
+    }
+
+    fn load_matches(&mut self) {
+        let remaining = self.target - self.prefix.iter().map(|i| self.nums[*i]).sum::<isize>();
+        let start = match (self.prefix.last(), self.reuse) {
+            (None, _) => 0,
+            (Some(last), Reuse::Allowed) => *last,
+            (Some(last), Reuse::Forbidden) => last + 1
+        };
+        if let Some(positions) = self.by_value.get(&remaining) {
+            for j in positions.iter().rev().filter(|j| **j >= start) {
+                let mut indices = self.prefix.clone();
+                indices.push(*j);
+                self.ready.push(KSum::new(self.nums, indices));
+            }
+        }
+    }
+
+    fn advance(&mut self) {
+        match (0..self.prefix.len()).rev().find(|i| self.prefix[*i] < self.max_at(*i)) {
+            None => self.done = true,
+            Some(i) => {
+                self.prefix[i] += 1;
+                for j in i + 1..self.prefix.len() {
+                    self.prefix[j] = if self.reuse == Reuse::Allowed {self.prefix[j - 1]} else {self.prefix[j - 1] + 1};
                 }
             }
         }
     }
-    Ok("Failed".to_string())
+}
+
+impl <'a> Iterator for KSums<'a> {
+    type Item = KSum;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(sum) = self.ready.pop() {
+                return Some(sum);
+            }
+            if self.done {
+                return None;
+            }
+            self.load_matches();
+            self.advance();
+        }
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{personal_input, InputFile};
+
+    const EXAMPLE: [isize; 6] = [1721, 979, 366, 299, 675, 1456];
+
+    #[test]
+    fn test_example() {
+        assert_eq!(solve_1("fixtures/day1_ex.txt").unwrap(), "1721 + 299 == 2020; 1721 * 299 == 514579");
+        assert_eq!(solve_2("fixtures/day1_ex.txt").unwrap(), "979 + 366 + 675 == 2020; 979 * 366 * 675 == 241861950");
+    }
+
+    #[test]
+    fn test_find_k_sum() {
+        let pair = find_k_sum(&EXAMPLE, 2, 2020, Reuse::Forbidden).unwrap();
+        assert_eq!(pair, KSum {indices: vec![0, 3], values: vec![1721, 299]});
+        assert_eq!(pair.product(), 514579);
+        assert_eq!(find_k_sum(&EXAMPLE, 3, 2020, Reuse::Forbidden).unwrap().indices, vec![1, 2, 4]);
+        assert_eq!(find_k_sum(&EXAMPLE, 1, 366, Reuse::Forbidden).unwrap().indices, vec![2]);
+        assert_eq!(find_k_sum(&EXAMPLE, 4, 979 + 366 + 299 + 1456, Reuse::Forbidden).unwrap().indices, vec![1, 2, 3, 5]);
+        assert_eq!(find_k_sum(&EXAMPLE, 2, 5, Reuse::Forbidden), None);
+        assert_eq!(find_k_sum(&EXAMPLE, 7, 2020, Reuse::Forbidden), None);
+        assert_eq!(find_k_sum(&[], 3, 0, Reuse::Allowed), None);
+    }
+
+    #[test]
+    fn test_reuse() {
+        let nums = [1010, 5, 673, 7];
+        assert_eq!(find_k_sum(&nums, 2, 2020, Reuse::Forbidden), None);
+        assert_eq!(find_k_sum(&nums, 2, 2020, Reuse::Allowed).unwrap().indices, vec![0, 0]);
+        assert_eq!(find_k_sum(&nums, 3, 2019, Reuse::Forbidden), None);
+        assert_eq!(find_k_sum(&nums, 3, 2019, Reuse::Allowed).unwrap().values, vec![673, 673, 673]);
+        assert_eq!(find_k_sum(&[1010, 1010], 2, 2020, Reuse::Forbidden).unwrap().indices, vec![0, 1]);
+    }
+
+    #[test]
+    fn test_no_solution() {
+        let input = InputFile::from_reader("1\n2\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), "day 1: no solution: no 2 entries sum to 2020");
+    }
+
+    fn all_indices(nums: &[isize], k: usize, target: isize, reuse: Reuse) -> Vec<Vec<usize>> {
+        all_k_sums(nums, k, target, reuse).map(|sum| sum.indices).collect()
+    }
+
+    #[test]
+    fn test_all_k_sums() {
+        let nums = [1, 2, 3, 4, 5];
+        assert_eq!(all_indices(&nums, 2, 6, Reuse::Forbidden), vec![vec![0, 4], vec![1, 3]]);
+        assert_eq!(all_indices(&nums, 2, 6, Reuse::Allowed), vec![vec![0, 4], vec![1, 3], vec![2, 2]]);
+        assert_eq!(all_indices(&nums, 3, 9, Reuse::Forbidden), vec![vec![0, 2, 4], vec![1, 2, 3]]);
+        assert_eq!(all_indices(&nums, 1, 4, Reuse::Forbidden), vec![vec![3]]);
+        assert_eq!(all_indices(&nums, 0, 0, Reuse::Forbidden), vec![Vec::<usize>::new()]);
+        assert_eq!(all_indices(&nums, 6, 15, Reuse::Forbidden), Vec::<Vec<usize>>::new());
+        assert_eq!(all_indices(&[1010, 1010, 1010], 2, 2020, Reuse::Forbidden), vec![vec![0, 1], vec![0, 2], vec![1, 2]]);
+        let products: Vec<isize> = all_k_sums(&EXAMPLE, 2, 2020, Reuse::Forbidden).map(|sum| sum.product()).collect();
+        assert_eq!(products, vec![514579]);
+    }
+
+    #[test]
+    fn test_count_k_sums() {
+        let nums = [1, 2, 3, 4, 5];
+        for (k, target) in [(0, 0), (1, 4), (2, 6), (3, 9), (3, 8), (4, 12), (6, 15)].iter() {
+            for reuse in [Reuse::Forbidden, Reuse::Allowed].iter() {
+                assert_eq!(count_k_sums(&nums, *k, *target, *reuse), all_k_sums(&nums, *k, *target, *reuse).count());
+            }
+        }
+        assert_eq!(count_k_sums(&[1010, 1010, 1010], 2, 2020, Reuse::Forbidden), 3);
+        assert_eq!(count_k_sums_in("fixtures/day1_ex.txt", 3, 2020, Reuse::Forbidden).unwrap(), 1);
+        assert_eq!(all_k_sums_in("fixtures/day1_ex.txt", 3, 2020, Reuse::Forbidden).unwrap()[0].product(), 241861950);
+    }
 
     #[test]
     fn test_1() {
-        assert_eq!(solve_1().unwrap().as_str(), "1477 + 543 == 2020; 1477 * 543 == 802011")
+        if let Some(input) = personal_input(1) {
+            assert_eq!(solve_1(input.as_str()).unwrap().as_str(), "1477 + 543 == 2020; 1477 * 543 == 802011")
+        }
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(solve_2().unwrap().as_str(), "422 + 577 + 1021 == 2020; 422 * 577 * 1021 == 248607374")
+        if let Some(input) = personal_input(1) {
+            assert_eq!(solve_2(input.as_str()).unwrap().as_str(), "422 + 577 + 1021 == 2020; 422 * 577 * 1021 == 248607374")
+        }
     }
 
 }
\ No newline at end of file
@@ -1,28 +1,35 @@
-use advent_code_lib::all_lines;
-use std::io;
 use bits::BitArray;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
-    let (count1, count3) = count_jolt_jumps(filename)?;
+const DAY: usize = 10;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    let (count1, count3) = count_jolt_jumps(&make_joltage_vec(filename)?);
     Ok((count1 * count3).to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
-    Ok(count_arrangements(filename)?.to_string())
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    Ok(count_arrangements(&make_joltage_vec(filename)?).to_string())
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(10, 1, solve_1);
+    registry.add(10, 2, solve_2);
 }
 
-fn make_joltage_vec(filename: &str) -> io::Result<Vec<usize>> {
+pub fn make_joltage_vec(filename: &str) -> AdventResult<Vec<usize>> {
     let mut nums = vec![0];
-    all_lines(filename)?
-        .map(|line| line.parse::<usize>().unwrap())
-        .for_each(|joltage| nums.push(joltage));
+    for (line_num, line) in numbered_lines(filename)? {
+        nums.push(parse_num(line.as_str()).at_line(DAY, line_num, line.as_str())?);
+    }
     nums.sort();
     nums.push(nums.last().unwrap() + 3);
     Ok(nums)
 }
 
-fn count_jolt_jumps(filename: &str) -> io::Result<(usize, usize)> {
-    let nums = make_joltage_vec(filename)?;
+pub fn count_jolt_jumps(nums: &[usize]) -> (usize, usize) {
     let mut count1 = 0;
     let mut count3 = 0;
     for i in 0..nums.len() - 1 {
@@ -30,7 +37,7 @@ fn count_jolt_jumps(filename: &str) -> io::Result<(usize, usize)> {
         if diff == 1 {count1 += 1;}
         if diff == 3 {count3 += 1;}
     }
-    Ok((count1, count3))
+    (count1, count3)
 }
 
 fn jolt_jump_ok(nums: &[usize], first: usize, second: usize) -> bool {
@@ -43,14 +50,13 @@ fn jolt_jump_ok(nums: &[usize], first: usize, second: usize) -> bool {
 // - Find all possibilities of current and predecessors
 // - Multiply
 // Implement with dynamic programming
-fn count_arrangements(filename: &str) -> io::Result<usize> {
-    let nums = make_joltage_vec(filename)?;
+pub fn count_arrangements(nums: &[usize]) -> usize {
     let mut results = vec![1_usize];
     for i in 1..nums.len() - 1 {
         let last = find_last_stable(&nums, i);
         results.push(results[last] * num_valid_in_window(&nums, last+1, i));
     }
-    Ok(*results.last().unwrap())
+    *results.last().unwrap()
 }
 
 fn find_last_stable(nums: &[usize], index_to_remove: usize) -> usize {
@@ -88,27 +94,27 @@ mod tests {
 
     #[test]
     fn test_ex_1_1() {
-        assert_eq!(count_jolt_jumps("in/day10_ex1.txt").unwrap(), (7, 5));
+        assert_eq!(count_jolt_jumps(&make_joltage_vec("fixtures/day10_ex1.txt").unwrap()), (7, 5));
     }
 
     #[test]
     fn text_ex_2_1() {
-        assert_eq!(count_jolt_jumps("in/day10_ex2.txt").unwrap(), (22, 10));
+        assert_eq!(count_jolt_jumps(&make_joltage_vec("fixtures/day10_ex2.txt").unwrap()), (22, 10));
     }
 
     #[test]
     fn test_ex_1_2() {
-        assert_eq!(count_arrangements("in/day10_ex1.txt").unwrap(), 8);
+        assert_eq!(count_arrangements(&make_joltage_vec("fixtures/day10_ex1.txt").unwrap()), 8);
     }
 
     #[test]
     fn test_ex_2_2() {
-        assert_eq!(count_arrangements("in/day10_ex2.txt").unwrap(), 19208);
+        assert_eq!(count_arrangements(&make_joltage_vec("fixtures/day10_ex2.txt").unwrap()), 19208);
     }
 
     #[test]
     fn test_self_1() {
-        assert_eq!(count_arrangements("in/day10_self1.txt").unwrap(), 4);
+        assert_eq!(count_arrangements(&make_joltage_vec("fixtures/day10_self1.txt").unwrap()), 4);
     }
 
     #[test]
@@ -147,7 +153,7 @@ mod tests {
         // 2, 4
         // 3, 4
         // 2, 3, 4
-        assert_eq!(count_arrangements("in/day10_self2.txt").unwrap(), 7)
+        assert_eq!(count_arrangements(&make_joltage_vec("fixtures/day10_self2.txt").unwrap()), 7)
     }
 
     #[test]
@@ -161,7 +167,7 @@ mod tests {
         // Delete 1: 2, 3, 4, 5 (total: 1)
         //
         // Turns out I undercounted by 3, as there are 14 rather than 11.
-        assert_eq!(count_arrangements("in/day10_self3.txt").unwrap(), 14);
+        assert_eq!(count_arrangements(&make_joltage_vec("fixtures/day10_self3.txt").unwrap()), 14);
     }
 }
 
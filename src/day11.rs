@@ -1,20 +1,30 @@
-use std::{io, mem};
-use advent_code_lib::{all_lines, Dir, Position};
+use std::mem;
+use advent_code_lib::{Dir, Position};
 use std::fmt::{Display, Formatter, Error};
 use enum_iterator::IntoEnumIterator;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext};
+use crate::input::numbered_lines;
+
+const DAY: usize = 11;
 
 const OCCUPIED: char = '#';
 const FLOOR: char = '.';
 const EMPTY: char = 'L';
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     Ok(num_occupied_at_stable(puzzle_1_iter(GameOfSeats::from(filename)?)).to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     Ok(num_occupied_at_stable(puzzle_2_iter(GameOfSeats::from(filename)?)).to_string())
 }
 
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(11, 1, solve_1);
+    registry.add(11, 2, solve_2);
+}
+
 pub fn num_occupied_at_stable(iter: GameOfSeatsIterator) -> usize {
     iter.last().unwrap().num_occupied()
 }
@@ -53,12 +63,17 @@ impl Display for GameOfSeats {
 }
 
 impl GameOfSeats {
-    pub fn from(filename: &str) -> io::Result<Self> {
-        Ok(GameOfSeats {
-            seating: all_lines(filename)?
-                .map(|line| line.chars().collect())
-                .collect()
-        })
+    pub fn from(filename: &str) -> AdventResult<Self> {
+        let mut seating: Vec<Vec<char>> = Vec::new();
+        for (line_num, line) in numbered_lines(filename)? {
+            let row = parse_row(line.as_str(), seating.first().map(|r| r.len())).at_line(DAY, line_num, line.as_str())?;
+            seating.push(row);
+        }
+        if seating.is_empty() {
+            Err(AdventError::bad_input(DAY, "no seats"))
+        } else {
+            Ok(GameOfSeats {seating})
+        }
     }
 
     pub fn height(&self) -> usize {self.seating.len()}
@@ -146,25 +161,36 @@ impl Iterator for GameOfSeatsIterator {
     }
 }
 
+fn parse_row(line: &str, width: Option<usize>) -> Result<Vec<char>,String> {
+    if let Some(c) = line.chars().find(|c| ![OCCUPIED, FLOOR, EMPTY].contains(c)) {
+        Err(format!("Unrecognized seat '{}'", c))
+    } else if width.map_or(false, |width| width != line.len()) {
+        Err(format!("expected {} seats, found {}", width.unwrap(), line.len()))
+    } else {
+        Ok(line.chars().collect())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn test_create() {
-        let start = GameOfSeats::from("in/day11_ex1.txt").unwrap();
+        let start = GameOfSeats::from("fixtures/day11_ex1.txt").unwrap();
         assert_eq!(start.to_string(), EXPECTED_1[0]);
     }
 
     #[test]
-    fn test_example_1() -> io::Result<()> {
-        test_example(puzzle_1_iter(GameOfSeats::from("in/day11_ex1.txt")?), &EXPECTED_1);
+    fn test_example_1() -> AdventResult<()> {
+        test_example(puzzle_1_iter(GameOfSeats::from("fixtures/day11_ex1.txt")?), &EXPECTED_1);
         Ok(())
     }
 
     #[test]
-    fn test_example_2() -> io::Result<()> {
-        test_example(puzzle_2_iter(GameOfSeats::from("in/day11_ex1.txt")?), &EXPECTED_2);
+    fn test_example_2() -> AdventResult<()> {
+        test_example(puzzle_2_iter(GameOfSeats::from("fixtures/day11_ex1.txt")?), &EXPECTED_2);
         Ok(())
     }
 
@@ -177,12 +203,12 @@ mod tests {
 
     #[test]
     fn test_solve_1() {
-        assert_eq!(solve_1("in/day11_ex1.txt").unwrap(), "37");
+        assert_eq!(solve_1("fixtures/day11_ex1.txt").unwrap(), "37");
     }
 
     #[test]
     fn test_solve_2() {
-        assert_eq!(solve_2("in/day11_ex1.txt").unwrap(), "26");
+        assert_eq!(solve_2("fixtures/day11_ex1.txt").unwrap(), "26");
     }
 
     const EXPECTED_1: [&'static str; 6] = [
@@ -332,4 +358,16 @@ LLL###LLL#
 #.L#LL#.L#
 "];
 
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bad_seats() {
+        for (text, error) in [
+            ("L.L\nL?L\n", "day 11, line 2: Unrecognized seat '?' in `L?L`"),
+            ("L.L\nL.\n", "day 11, line 2: expected 3 seats, found 2 in `L.`"),
+            ("", "day 11: bad input: no seats")
+        ].iter() {
+            let input = InputFile::from_reader(text.as_bytes()).unwrap();
+            assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), *error);
+        }
+    }
+}
@@ -1,12 +1,17 @@
-use advent_code_lib::{Position, Dir, for_each_line, normalize_degrees, DirType};
-use std::io;
+use advent_code_lib::{Position, Dir, normalize_degrees, DirType};
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 12;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     let mut ship_pos = Position::new();
     let mut ship_heading = Dir::E;
-    for_each_line(filename, |line| Ok({
-        interpret_move_puzzle_1(&mut ship_pos, &mut ship_heading, line);
-    }))?;
+    for (line_num, line) in numbered_lines(filename)? {
+        interpret_move_puzzle_1(&mut ship_pos, &mut ship_heading, line.as_str())
+            .at_line(DAY, line_num, line.as_str())?;
+    }
     Ok(manhattan_str(ship_pos))
 }
 
@@ -14,15 +19,21 @@ pub fn manhattan_str(p: Position) -> String {
     (p.col.abs() + p.row.abs()).to_string()
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     let mut ship = Position::new();
     let mut waypoint = Position::from((10, -1));
-    for_each_line(filename, |line| Ok({
-        interpret_move_puzzle_2(&mut ship, &mut waypoint, line);
-    }))?;
+    for (line_num, line) in numbered_lines(filename)? {
+        interpret_move_puzzle_2(&mut ship, &mut waypoint, line.as_str())
+            .at_line(DAY, line_num, line.as_str())?;
+    }
     Ok(manhattan_str(ship))
 }
 
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(12, 1, solve_1);
+    registry.add(12, 2, solve_2);
+}
+
 pub fn jump(p: &mut Position, dir: Dir, dist: isize) {
     *p += dir.position_offset() * dist;
 }
@@ -34,15 +45,15 @@ pub fn rotate_waypoint(p: &mut Position, degrees: isize) {
     }
 }
 
-pub fn decode_line(line: &str) -> (char,isize) {
+pub fn decode_line(line: &str) -> Result<(char,isize),String> {
     let mut chars = line.chars();
-    let instruction = chars.next().unwrap();
-    let value = chars.collect::<String>().parse::<isize>().unwrap();
-    (instruction, value)
+    let instruction = chars.next().ok_or("empty instruction")?;
+    let value = parse_num::<isize>(chars.as_str())?;
+    Ok((instruction, value))
 }
 
-pub fn interpret_move_puzzle_1(ship_pos: &mut Position, ship_heading: &mut Dir, line: &str) {
-    let (instruction, value) = decode_line(line);
+pub fn interpret_move_puzzle_1(ship_pos: &mut Position, ship_heading: &mut Dir, line: &str) -> Result<(),String> {
+    let (instruction, value) = decode_line(line)?;
     match instruction {
         'N' => jump(ship_pos, Dir::N, value),
         'S' => jump(ship_pos, Dir::S, value),
@@ -51,12 +62,13 @@ pub fn interpret_move_puzzle_1(ship_pos: &mut Position, ship_heading: &mut Dir,
         'F' => jump(ship_pos, *ship_heading, value),
         'L' => *ship_heading = ship_heading.rotated_degrees(-value),
         'R' => *ship_heading = ship_heading.rotated_degrees(value),
-        _ => panic!("Unrecognized instruction")
+        _ => return Err(format!("Unrecognized instruction '{}'", instruction))
     }
+    Ok(())
 }
 
-pub fn interpret_move_puzzle_2(s: &mut Position, w: &mut Position, line: &str) {
-    let (instruction, value) = decode_line(line);
+pub fn interpret_move_puzzle_2(s: &mut Position, w: &mut Position, line: &str) -> Result<(),String> {
+    let (instruction, value) = decode_line(line)?;
     match instruction {
         'N' => jump(w, Dir::N, value),
         'S' => jump(w, Dir::S, value),
@@ -65,27 +77,41 @@ pub fn interpret_move_puzzle_2(s: &mut Position, w: &mut Position, line: &str) {
         'F' => *s += *w * value,
         'L' => rotate_waypoint(w, -value),
         'R' => rotate_waypoint(w, value),
-        _ => panic!("Unrecognized instruction")
+        _ => return Err(format!("Unrecognized instruction '{}'", instruction))
     }
+    Ok(())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::personal_input;
 
     #[test]
     pub fn test_1() {
-        assert_eq!(solve_1("in/day12_ex.txt").unwrap(), "25");
+        assert_eq!(solve_1("fixtures/day12_ex.txt").unwrap(), "25");
     }
 
     #[test]
     pub fn test_solution_1() {
-        assert_eq!(solve_1("in/day12.txt").unwrap(), "441");
+        if let Some(input) = personal_input(12) {
+            assert_eq!(solve_1(input.as_str()).unwrap(), "441");
+        }
     }
 
     #[test]
     pub fn test_2() {
-        assert_eq!(solve_2("in/day12_ex.txt").unwrap(), "286");
+        assert_eq!(solve_2("fixtures/day12_ex.txt").unwrap(), "286");
+    }
+
+    #[test]
+    pub fn test_decode_line() {
+        assert_eq!(decode_line("F10").unwrap(), ('F', 10));
+        assert_eq!(decode_line("").unwrap_err(), "empty instruction");
+        assert_eq!(decode_line("Fx").unwrap_err(), "`x` is not a number");
+        let mut ship = Position::new();
+        let mut heading = Dir::E;
+        assert_eq!(interpret_move_puzzle_1(&mut ship, &mut heading, "Q3").unwrap_err(), "Unrecognized instruction 'Q'");
     }
 
     #[test]
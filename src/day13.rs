@@ -1,54 +1,77 @@
-use std::io;
-use advent_code_lib::all_lines;
 use num::Integer;
 use bare_metal_modulo::{MNum, ModNum};
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 13;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     let (earliest_departure, busses) = puzzle_1_inputs(filename)?;
-    let (best, wait) = best_bus_and_wait(&busses, earliest_departure);
+    let (best, wait) = best_bus_and_wait(&busses, earliest_departure)?;
     Ok((best * wait).to_string())
 }
 
-pub fn solve_2() -> io::Result<String> {
-    let line_2 = all_lines("in/day13.txt")?.nth(1).unwrap();
-    Ok(solve_2_str(line_2.as_str()).to_string())
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    earliest_alignment(&puzzle_2_inputs(filename)?)
+        .map(|t| t.to_string())
+        .map_err(|e| AdventError::no_solution(DAY, e.as_str()))
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(13, 1, solve_1);
+    registry.add(13, 2, solve_2);
 }
 
-fn puzzle_1_inputs(filename: &str) -> io::Result<(usize, Vec<usize>)> {
-    let mut lines = all_lines(filename)?;
-    let earliest_departure = lines.next().unwrap().parse::<usize>().unwrap();
-    let busses = lines.next().unwrap().split(',')
+pub fn puzzle_1_inputs(filename: &str) -> AdventResult<(usize, Vec<usize>)> {
+    let mut lines = numbered_lines(filename)?;
+    let (num_1, line_1) = lines.next().ok_or_else(|| AdventError::bad_input(DAY, "missing departure line"))?;
+    let earliest_departure = parse_num(line_1.as_str()).at_line(DAY, num_1, line_1.as_str())?;
+    let (num_2, line_2) = lines.next().ok_or_else(|| AdventError::bad_input(DAY, "missing bus line"))?;
+    let busses = line_2.split(',')
         .filter(|n| *n != "x")
-        .map(|n| n.parse::<usize>().unwrap())
-        .collect();
+        .map(|n| parse_num(n).at_line(DAY, num_2, line_2.as_str()))
+        .collect::<AdventResult<_>>()?;
     Ok((earliest_departure, busses))
 }
 
-fn best_bus_and_wait(busses: &[usize], earliest_departure: usize) -> (usize, usize) {
+pub fn puzzle_2_inputs(filename: &str) -> AdventResult<Vec<(i128, i128)>> {
+    let (line_num, line_2) = numbered_lines(filename)?.nth(1)
+        .ok_or_else(|| AdventError::bad_input(DAY, "missing bus line"))?;
+    bus_offsets(line_2.as_str()).at_line(DAY, line_num, line_2.as_str())
+}
+
+pub fn best_bus_and_wait(busses: &[usize], earliest_departure: usize) -> AdventResult<(usize, usize)> {
     let (departure, best_bus) = busses.iter()
         .map(|bus| (bus_departure(*bus, earliest_departure), *bus))
-        .min().unwrap();
-    (best_bus, departure - earliest_departure)
+        .min()
+        .ok_or_else(|| AdventError::no_solution(DAY, "no busses in service"))?;
+    Ok((best_bus, departure - earliest_departure))
 }
 
 fn bus_departure(bus: usize, earliest_departure: usize) -> usize {
     earliest_departure + bus - earliest_departure.mod_floor(&bus)
 }
 
+// Each bus id with its position in the list.
+pub fn bus_offsets(input_line: &str) -> Result<Vec<(i128, i128)>,String> {
+    input_line
+        .split(',')
+        .enumerate()
+        .filter(|(_, s)| *s != "x")
+        .map(|(i, s)| parse_num::<i128>(s).map(|bus| (i as i128, bus)))
+        .collect()
+}
+
+// Bus `b` at position `i` must depart at a time congruent to `-i` mod `b`.
 // Uses Extended Greatest Common Divisor algorithm
 // I learned about all this from:
 // https://byorgey.wordpress.com/2020/02/15/competitive-programming-in-haskell-modular-arithmetic-part-1/
 // https://byorgey.wordpress.com/2020/03/03/competitive-programming-in-haskell-modular-arithmetic-part-2/
-fn solve_2_str(input_line: &str) -> i128 {
-    ModNum::chinese_remainder_system(
-        input_line
-            .split(',')
-            .enumerate()
-            .filter(|(_, s)| *s != "x")
-            .map(|(i, s)| -ModNum::new(i as i128, s.parse::<i128>().unwrap()))
-            .inspect(|m| println!("{:?}", m)))
-        .unwrap()
-        .a()
+pub fn earliest_alignment(offsets: &[(i128, i128)]) -> Result<i128,String> {
+    ModNum::chinese_remainder_system(offsets.iter().map(|(i, bus)| -ModNum::new(*i, *bus)))
+        .map(|m| m.a())
+        .ok_or("bus ids have no common solution".to_string())
 }
 
 /*
@@ -72,6 +95,11 @@ c = 1 * 13 * -1 + 0 = -13
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
+
+    fn solve_2_str(input_line: &str) -> Result<i128,String> {
+        earliest_alignment(&bus_offsets(input_line)?)
+    }
 
     #[test]
     fn test_departure_1() {
@@ -81,21 +109,21 @@ mod tests {
 
     #[test]
     fn test_calculation_1() {
-        let (bus, wait) = best_bus_and_wait(&[7, 13, 59, 31, 19], 939);
+        let (bus, wait) = best_bus_and_wait(&[7, 13, 59, 31, 19], 939).unwrap();
         assert_eq!(bus, 59);
         assert_eq!(wait, 5);
     }
 
     #[test]
     fn test_puzzle_1_inputs() {
-        let (depart, busses) = puzzle_1_inputs("in/day13_ex.txt").unwrap();
+        let (depart, busses) = puzzle_1_inputs("fixtures/day13_ex.txt").unwrap();
         assert_eq!(depart, 939);
         assert_eq!(busses, vec![7,13,59,31,19]);
     }
 
     #[test]
     fn test_solve_1() {
-        assert_eq!(solve_1("in/day13_ex.txt").unwrap(), "295");
+        assert_eq!(solve_1("fixtures/day13_ex.txt").unwrap(), "295");
     }
 
     #[test]
@@ -106,7 +134,17 @@ mod tests {
             ("67,x,7,59,61", 779210),
             ("67,7,x,59,61", 1261476),
             ("1789,37,47,1889", 1202161486)] {
-            assert_eq!(solve_2_str(line), *goal);
+            assert_eq!(solve_2_str(line).unwrap(), *goal);
         }
+        assert_eq!(solve_2_str("17,y,13").unwrap_err(), "`y` is not a number");
+    }
+
+    #[test]
+    fn test_missing_lines() {
+        let input = InputFile::from_reader("".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), "day 13: bad input: missing departure line");
+        let input = InputFile::from_reader("939\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), "day 13: bad input: missing bus line");
+        assert_eq!(solve_2(input.filename()).unwrap_err().to_string(), "day 13: bad input: missing bus line");
     }
 }
@@ -1,15 +1,23 @@
-use std::io;
-use advent_code_lib::all_lines;
 use std::collections::BTreeMap;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 14;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     Mask1::from("").solve(filename)
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     Mask2::from("").solve(filename)
 }
 
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(14, 1, solve_1);
+    registry.add(14, 2, solve_2);
+}
+
 pub trait Solver {
     fn update_mask(&mut self, line: &str);
     fn update_mem(&self, idx: u64, val: u64, mem: &mut BTreeMap<u64,u64>);
@@ -19,26 +27,35 @@ pub trait Solver {
         line.chars().skip_while(|c| "mask = ".contains(*c)).for_each(|c| self.add(c));
     }
 
-    fn solve(&mut self, filename: &str) -> io::Result<String> {
+    fn solve(&mut self, filename: &str) -> AdventResult<String> {
         let mut mem = BTreeMap::new();
-        all_lines(filename)?.for_each(|line| {
+        for (line_num, line) in numbered_lines(filename)? {
             if line.starts_with("mask") {
+                check_mask(line.as_str()).at_line(DAY, line_num, line.as_str())?;
                 self.update_mask(line.as_str());
             } else {
-                let (idx, val) = split_mem(line.as_str());
+                let (idx, val) = split_mem(line.as_str()).at_line(DAY, line_num, line.as_str())?;
                 self.update_mem(idx, val, &mut mem);
             }
-        });
+        }
         let mem_sum: u64 = mem.values().sum();
         Ok(mem_sum.to_string())
     }
 }
 
-fn split_mem(line: &str) -> (u64, u64) {
+fn check_mask(line: &str) -> Result<(),String> {
+    match line.chars().skip_while(|c| "mask = ".contains(*c)).find(|c| !"X01".contains(*c)) {
+        Some(c) => Err(format!("mask char '{}' unknown", c)),
+        None => Ok(())
+    }
+}
+
+pub fn split_mem(line: &str) -> Result<(u64, u64),String> {
     let mut tokens = line.split(&['[', ']', '=', ' '][..]).filter(|t| t.len() > 0).skip(1);
-    let idx = tokens.next().unwrap().parse::<u64>().unwrap();
-    let val = tokens.next().unwrap().parse::<u64>().unwrap();
-    (idx, val)
+    let mut next_num = || tokens.next().ok_or("expected `mem[address] = value`".to_string()).and_then(parse_num::<u64>);
+    let idx = next_num()?;
+    let val = next_num()?;
+    Ok((idx, val))
 }
 
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
@@ -136,6 +153,7 @@ fn add_to_all(masks: &mut Vec<Mask1>, c: char) {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::personal_input;
 
     fn make_ones(num_ones: u32) -> u64 {
         2_u64.pow(num_ones) - 1
@@ -143,7 +161,16 @@ mod tests {
 
     #[test]
     fn test_1() {
-        assert_eq!(solve_1("in/day14_ex.txt").unwrap(), "165");
+        assert_eq!(solve_1("fixtures/day14_ex.txt").unwrap(), "165");
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(split_mem("mem[8] = 11").unwrap(), (8, 11));
+        assert_eq!(split_mem("mem[8]").unwrap_err(), "expected `mem[address] = value`");
+        assert_eq!(split_mem("mem[q] = 11").unwrap_err(), "`q` is not a number");
+        assert!(check_mask("mask = XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X").is_ok());
+        assert_eq!(check_mask("mask = XX2X").unwrap_err(), "mask char '2' unknown");
     }
 
     #[test]
@@ -175,11 +202,15 @@ mod tests {
 
     #[test]
     fn test_solve_1() {
-        assert_eq!(solve_1("in/day14.txt").unwrap(), "17481577045893");
+        if let Some(input) = personal_input(14) {
+            assert_eq!(solve_1(input.as_str()).unwrap(), "17481577045893");
+        }
     }
 
     #[test]
     fn test_solve_2() {
-        assert_eq!(solve_2("in/day14.txt").unwrap(), "4160009892257");
+        if let Some(input) = personal_input(14) {
+            assert_eq!(solve_2(input.as_str()).unwrap(), "4160009892257");
+        }
     }
 }
\ No newline at end of file
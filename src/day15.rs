@@ -1,14 +1,37 @@
 use std::collections::HashMap;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1() -> String {
-    ElfGame::elf(&PUZZLE_INPUT, 2020).to_string()
+const DAY: usize = 15;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    Ok(ElfGame::elf(&starting_nums(filename)?, 2020).to_string())
+}
+
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    Ok(ElfGame::elf(&starting_nums(filename)?, 30000000).to_string())
 }
 
-pub fn solve_2() -> String {
-    ElfGame::elf(&PUZZLE_INPUT, 30000000).to_string()
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(15, 1, solve_1);
+    registry.add(15, 2, solve_2);
+}
+
+pub fn starting_nums(filename: &str) -> AdventResult<Vec<usize>> {
+    let mut nums = Vec::new();
+    for (line_num, line) in numbered_lines(filename)? {
+        for n in line.split(',').map(|n| n.trim()).filter(|n| !n.is_empty()) {
+            nums.push(parse_num(n).at_line(DAY, line_num, line.as_str())?);
+        }
+    }
+    if nums.is_empty() {
+        return Err(AdventError::bad_input(DAY, "no starting numbers"));
+    }
+    Ok(nums)
 }
 
-struct ElfGame {
+pub struct ElfGame {
     num2last: HashMap<usize,(usize,usize)>,
     spoken: usize
 }
@@ -41,11 +64,10 @@ impl ElfGame {
     }
 }
 
-const PUZZLE_INPUT: [usize; 7] = [2,0,1,7,4,14,18];
-
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn test_1() {
@@ -61,4 +83,13 @@ mod tests {
             assert_eq!(ElfGame::elf(nums, 2020), *target);
         }
     }
+
+    #[test]
+    fn test_starting_nums() {
+        let input = InputFile::from_reader("0,3,6\n".as_bytes()).unwrap();
+        assert_eq!(starting_nums(input.filename()).unwrap(), vec![0, 3, 6]);
+        assert_eq!(solve_1(input.filename()).unwrap(), "436");
+        let input = InputFile::from_reader("\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), "day 15: bad input: no starting numbers");
+    }
 }
\ No newline at end of file
@@ -1,25 +1,33 @@
 use std::collections::{BTreeMap, BTreeSet};
-use std::io;
-use advent_code_lib::all_lines;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1() -> io::Result<String> {
-    Ok(Notes::from("in/day16.txt", true)?.nearby_ticket_scanning_error_rate().to_string())
+const DAY: usize = 16;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    Ok(Notes::from(filename, true)?.nearby_ticket_scanning_error_rate().to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     Ok(Notes::from(filename, false)?.departure_product().to_string())
 }
 
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(16, 1, solve_1);
+    registry.add(16, 2, solve_2);
+}
+
 #[derive(Debug,Clone,Eq,PartialEq)]
 struct Constraints {
     fields: BTreeMap<String,((usize,usize),(usize,usize))>
 }
 
 impl Constraints {
-    pub fn from(lines: &mut impl Iterator<Item=String>) -> Self {
-        Constraints {fields: lines.take_while(|line| line.len() > 0)
-            .map(|line| parse_field_line(line.as_str()))
-            .collect()}
+    pub fn from(lines: &mut impl Iterator<Item=(usize,String)>) -> AdventResult<Self> {
+        Ok(Constraints {fields: lines.take_while(|(_, line)| !line.is_empty())
+            .map(|(line_num, line)| parse_field_line(line.as_str()).at_line(DAY, line_num, line.as_str()))
+            .collect::<AdventResult<_>>()?})
     }
 
     pub fn names(&self) -> impl Iterator<Item=&String> {
@@ -41,24 +49,28 @@ impl Constraints {
 }
 
 #[derive(Debug,Clone,Eq,PartialEq)]
-struct Notes {
+pub struct Notes {
     fields: Constraints,
     my_ticket: Vec<usize>,
     nearby_tickets: Vec<Vec<usize>>
 }
 
 impl Notes {
-    fn from(filename: &str, allow_invalid: bool) -> io::Result<Self> {
-        let mut lines = all_lines(filename)?;
-        let fields = Constraints::from(&mut lines.by_ref());
-        let my_ticket = parse_ticket_line(lines.by_ref()
-            .skip_while(|line| line.len() == 0 || line == "your ticket:")
-            .next().unwrap().as_str());
-        let nearby_tickets = lines
-            .skip_while(|line| line.len() == 0 || line == "nearby tickets:")
-            .map(|line| parse_ticket_line(line.as_str()))
-            .filter(|t| allow_invalid || fields.accepts_ticket(t))
-            .collect();
+    pub fn from(filename: &str, allow_invalid: bool) -> AdventResult<Self> {
+        let mut lines = numbered_lines(filename)?;
+        let fields = Constraints::from(&mut lines.by_ref())?;
+        let (line_num, line) = lines.by_ref()
+            .skip_while(|(_, line)| line.is_empty() || line == "your ticket:")
+            .next()
+            .ok_or_else(|| AdventError::bad_input(DAY, "missing your ticket"))?;
+        let my_ticket = parse_ticket_line(line.as_str()).at_line(DAY, line_num, line.as_str())?;
+        let mut nearby_tickets = Vec::new();
+        for (line_num, line) in lines.skip_while(|(_, line)| line.is_empty() || line == "nearby tickets:") {
+            let ticket = parse_ticket_line(line.as_str()).at_line(DAY, line_num, line.as_str())?;
+            if allow_invalid || fields.accepts_ticket(&ticket) {
+                nearby_tickets.push(ticket);
+            }
+        }
         Ok(Notes {fields, my_ticket, nearby_tickets})
     }
 
@@ -146,65 +158,85 @@ impl PotentialMatches {
     }
 }
 
-fn parse_field_line(line: &str) -> (String,((usize,usize),(usize,usize))) {
+pub fn parse_field_line(line: &str) -> Result<(String,((usize,usize),(usize,usize))),String> {
     let mut parts_colon = line.split(':');
     let field_name = parts_colon.next().unwrap().to_string();
-    let ns: Vec<_> = parts_colon.next().unwrap().split(&[' ', '-', 'o','r'][..])
+    let ranges = parts_colon.next().ok_or("expected `field: a-b or c-d`".to_string())?;
+    let ns: Vec<usize> = ranges.split(&[' ', '-', 'o','r'][..])
         .filter(|s| s.len() > 0)
-        .map(|s| s.parse().unwrap())
-        .collect();
-    (field_name, ((ns[0], ns[1]), (ns[2], ns[3])))
+        .map(parse_num)
+        .collect::<Result<_,_>>()?;
+    if ns.len() != 4 {
+        return Err("expected `field: a-b or c-d`".to_string());
+    }
+    Ok((field_name, ((ns[0], ns[1]), (ns[2], ns[3]))))
 }
 
-fn parse_ticket_line(line: &str) -> Vec<usize> {
-    line.split(',').map(|s| s.parse().unwrap()).collect()
+pub fn parse_ticket_line(line: &str) -> Result<Vec<usize>,String> {
+    line.split(',').map(parse_num).collect()
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::personal_input;
+    use crate::input::InputFile;
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse_field_line("class: 1-3 or 5-7").unwrap(), ("class".to_string(), ((1, 3), (5, 7))));
+        assert_eq!(parse_field_line("class 1-3").unwrap_err(), "expected `field: a-b or c-d`");
+        assert_eq!(parse_ticket_line("7,1,x").unwrap_err(), "`x` is not a number");
+        let input = InputFile::from_reader("class: 1-3 or 5-7\n\nyour ticket:\n7,1,14\n\nnearby tickets:\n7,3,47\n40,x\n".as_bytes()).unwrap();
+        assert_eq!(Notes::from(input.filename(), true).unwrap_err().to_string(),
+                   "day 16, line 8: `x` is not a number in `40,x`");
+    }
 
     #[test]
     fn test_ex_1() {
-        let notes = Notes::from("in/day16_ex1.txt", true).unwrap();
+        let notes = Notes::from("fixtures/day16_ex1.txt", true).unwrap();
         assert_eq!(notes.nearby_ticket_scanning_error_rate(), 71);
     }
 
     #[test]
     fn test_matches() {
-        let notes = Notes::from("in/day16_ex1.txt", true).unwrap();
+        let notes = Notes::from("fixtures/day16_ex1.txt", true).unwrap();
         [(1,true), (2,true), (3,true), (4,false), (5,true), (7,true), (8,false)].iter()
             .for_each(|(v,tf)| assert_eq!(notes.matches_range_for("class", *v), *tf));
     }
 
     #[test]
     fn test_invalid_values() {
-        let notes = Notes::from("in/day16_ex1.txt", true).unwrap();
+        let notes = Notes::from("fixtures/day16_ex1.txt", true).unwrap();
         assert_eq!(notes.invalid_values_for(&vec![40,4,50]), vec![4]);
     }
 
     #[test]
     fn test_field_positions() {
-        let notes = Notes::from("in/day16_ex2.txt", true).unwrap();
+        let notes = Notes::from("fixtures/day16_ex2.txt", true).unwrap();
         assert_eq!(notes.field_positions(), btreemap! {"class".to_string() => 1, "row".to_string() => 0, "seat".to_string() => 2});
     }
 
     #[test]
     fn test_my_fields() {
-        let notes = Notes::from("in/day16_ex2.txt", true).unwrap();
+        let notes = Notes::from("fixtures/day16_ex2.txt", true).unwrap();
         assert_eq!(notes.my_field_values(), btreemap! {"class".to_string() => 12, "row".to_string() => 11, "seat".to_string() => 13});
     }
 
     #[test]
     fn test_valid_field_positions() {
-        let notes = Notes::from("in/day16.txt", false).unwrap();
-        let unique_positions: BTreeSet<usize> = notes.field_positions().iter().map(|p| *p.1).collect();
-        assert_eq!(unique_positions.len(), notes.num_positions());
+        if let Some(input) = personal_input(16) {
+            let notes = Notes::from(input.as_str(), false).unwrap();
+            let unique_positions: BTreeSet<usize> = notes.field_positions().iter().map(|p| *p.1).collect();
+            assert_eq!(unique_positions.len(), notes.num_positions());
+        }
     }
 
     #[test]
     fn test_departures() {
-        let notes = Notes::from("in/day16.txt", false).unwrap();
-        assert_eq!(format!("{:?}", notes.my_departures()), r#"{"departure date": 101, "departure location": 53, "departure platform": 89, "departure station": 61, "departure time": 113, "departure track": 73}"#)
+        if let Some(input) = personal_input(16) {
+            let notes = Notes::from(input.as_str(), false).unwrap();
+            assert_eq!(format!("{:?}", notes.my_departures()), r#"{"departure date": 101, "departure location": 53, "departure platform": 89, "departure station": 61, "departure time": 113, "departure track": 73}"#)
+        }
     }
 }
\ No newline at end of file
@@ -1,27 +1,35 @@
 use std::collections::BTreeMap;
-use std::io;
-use advent_code_lib::for_each_line;
 use smallvec::SmallVec;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 17;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     Ok(after_n_cycles(ConwayCubes::from(filename, 3)?, 6).to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     Ok(after_n_cycles(ConwayCubes::from(filename, 4)?, 6).to_string())
 }
 
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(17, 1, solve_1);
+    registry.add(17, 2, solve_2);
+}
+
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
 pub enum State {
     ACTIVE, INACTIVE
 }
 
 impl State {
-    pub fn from(c: char) -> State {
+    pub fn from(c: char) -> Result<State,String> {
         match c {
-            '#' => State::ACTIVE,
-            '.' => State::INACTIVE,
-            _ => panic!("Unrecognized input character: '{}'", c)
+            '#' => Ok(State::ACTIVE),
+            '.' => Ok(State::INACTIVE),
+            _ => Err(format!("Unrecognized input character: '{}'", c))
         }
     }
 }
@@ -32,17 +40,21 @@ pub struct ConwayCubes {
 }
 
 impl ConwayCubes {
-    pub fn from(filename: &str, dimension: usize) -> io::Result<ConwayCubes> {
+    pub fn from(filename: &str, dimension: usize) -> AdventResult<ConwayCubes> {
         let mut cubes = ConwayCubes { cubes: BTreeMap::new() };
         let mut y = 0;
-        for_each_line(filename, |line| Ok({
+        for (line_num, line) in numbered_lines(filename)? {
             let mut x = 0;
             for c in line.chars() {
-                cubes.cubes.insert(PointND::new_zero_pad(&[x, y], dimension), State::from(c));
+                let state = State::from(c).at_line(DAY, line_num, line.as_str())?;
+                cubes.cubes.insert(PointND::new_zero_pad(&[x, y], dimension), state);
                 x += 1;
             }
             y += 1;
-        }))?;
+        }
+        if cubes.cubes.is_empty() {
+            return Err(AdventError::bad_input(DAY, "no cubes"));
+        }
         Ok(cubes)
     }
 
@@ -62,6 +74,15 @@ impl ConwayCubes {
         p.neighbors().filter(|n| self.state(n) == State::ACTIVE).count()
     }
 
+    pub fn cycle(&self) -> ConwayCubes {
+        ConwayCubes {
+            cubes: PointNDIterator::new(&self.min_point().prev_corner(), &self.max_point().next_corner())
+                .map(|p| (p.clone(), new_cell_state(self.state(&p),
+                                                    self.num_active_neighbors(&p))))
+                .collect()
+        }
+    }
+
     pub fn num_active(&self) -> usize {
         self.cubes.values().filter(|v| **v == State::ACTIVE).count()
     }
@@ -153,19 +174,10 @@ fn new_cell_state(cell: State, num_active_neighbors: usize) -> State {
     }
 }
 
-fn cycle(start: &ConwayCubes) -> ConwayCubes {
-    ConwayCubes {
-        cubes: PointNDIterator::new(&start.min_point().prev_corner(), &start.max_point().next_corner())
-            .map(|p| (p.clone(), new_cell_state(start.state(&p),
-                                                start.num_active_neighbors(&p))))
-            .collect()
-    }
-}
-
-fn after_n_cycles(start: ConwayCubes, n: usize) -> usize {
+pub fn after_n_cycles(start: ConwayCubes, n: usize) -> usize {
     let mut cubes = start;
     for _ in 0..n {
-        cubes = cycle(&cubes);
+        cubes = cubes.cycle();
     }
     cubes.num_active()
 }
@@ -173,6 +185,7 @@ fn after_n_cycles(start: ConwayCubes, n: usize) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn test_iterator() {
@@ -194,12 +207,12 @@ mod tests {
     #[test]
     fn test_puzzle_1() {
         let targets = [5, 11, 21, 38];
-        let mut cubes = ConwayCubes::from("in/day17_ex.txt", 3).unwrap();
+        let mut cubes = ConwayCubes::from("fixtures/day17_ex.txt", 3).unwrap();
         for t in 0..6 {
             if t < targets.len() {
                 assert_eq!(cubes.num_active(), targets[t]);
             }
-            cubes = cycle(&cubes);
+            cubes = cubes.cycle();
         }
         assert_eq!(cubes.num_active(), 112);
     }
@@ -207,7 +220,16 @@ mod tests {
     #[test]
     // Only run this unit test in release; it takes 48 seconds in debug on this laptop.
     fn test_puzzle_2() {
-        let cubes = ConwayCubes::from("in/day17_ex.txt", 4).unwrap();
+        let cubes = ConwayCubes::from("fixtures/day17_ex.txt", 4).unwrap();
         assert_eq!(after_n_cycles(cubes, 6), 848);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bad_input() {
+        let input = InputFile::from_reader("\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), "day 17: bad input: no cubes");
+        let input = InputFile::from_reader(".#\n#x\n".as_bytes()).unwrap();
+        assert_eq!(solve_2(input.filename()).unwrap_err().to_string(),
+                   "day 17, line 2: Unrecognized input character: 'x' in `#x`");
+    }
+}
@@ -1,7 +1,10 @@
-use std::io;
-use advent_code_lib::all_lines;
 use core::iter::Peekable;
 use std::str::Chars;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, LineContext};
+use crate::input::numbered_lines;
+
+const DAY: usize = 18;
 
 fn puzzle_1(line: &str) -> Evaluator<Chars> {
     Evaluator::new(line.chars(), Evaluator::grab_next_value)
@@ -11,41 +14,58 @@ fn puzzle_2(line: &str) -> Evaluator<Chars> {
     Evaluator::new(line.chars(), Evaluator::eval)
 }
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     solve(filename, puzzle_1)
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     solve(filename, puzzle_2)
 }
 
-pub fn solve(filename: &str, puzzle: fn(&str)->Evaluator<Chars>) -> io::Result<String> {
-    Ok(all_lines(filename)?
-        .map(|line| puzzle(line.as_str()).eval())
-        .sum::<usize>().to_string())
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(18, 1, solve_1);
+    registry.add(18, 2, solve_2);
+}
+
+pub fn solve(filename: &str, puzzle: fn(&str)->Evaluator<Chars>) -> AdventResult<String> {
+    let mut total = 0;
+    for (line_num, line) in numbered_lines(filename)? {
+        total += puzzle(line.as_str()).eval_all().at_line(DAY, line_num, line.as_str())?;
+    }
+    Ok(total.to_string())
 }
 
 pub struct Evaluator<I:Iterator<Item=char>> {
     chars: Peekable<I>,
-    puzzle: fn(&mut Evaluator<I>) -> usize
+    puzzle: fn(&mut Evaluator<I>) -> Result<usize,String>
 }
 
 impl <I:Iterator<Item=char>> Evaluator<I> {
-    pub fn new(chars: I, puzzle: fn(&mut Evaluator<I>) -> usize) -> Evaluator<I> {
+    pub fn new(chars: I, puzzle: fn(&mut Evaluator<I>) -> Result<usize,String>) -> Evaluator<I> {
         Evaluator {chars: chars.peekable(), puzzle}
     }
 
-    pub fn eval(&mut self) -> usize {
-        let mut total = self.grab_next_value();
+    // Evaluates a whole expression, rejecting anything left over.
+    pub fn eval_all(&mut self) -> Result<usize,String> {
+        let total = self.eval()?;
+        match self.chars.next() {
+            None => Ok(total),
+            Some(c) => Err(format!("Unexpected '{}'", c))
+        }
+    }
+
+    pub fn eval(&mut self) -> Result<usize,String> {
+        let mut total = self.grab_next_value()?;
         while !self.at_expr_end() {
-            total = match self.chars.next().unwrap() {
-                '+' => total + self.grab_next_value(),
-                '*' => total * (self.puzzle)(self),
-                ' ' => total,
-                _ => panic!("This shouldn't happen")
+            total = match self.chars.next() {
+                Some('+') => total + self.grab_next_value()?,
+                Some('*') => total * (self.puzzle)(self)?,
+                Some(' ') => total,
+                Some(c) => return Err(format!("Expected an operator, found '{}'", c)),
+                None => return Err("Unexpected end of expression".to_string())
             };
         }
-        total
+        Ok(total)
     }
 
     pub fn at_expr_end(&mut self) -> bool {
@@ -53,21 +73,22 @@ impl <I:Iterator<Item=char>> Evaluator<I> {
         peeked == None || *(peeked.unwrap()) == ')'
     }
 
-    pub fn grab_next_value(&mut self) -> usize {
-        let c = self.chars.next().unwrap();
-        match c {
-            '0'..='9' => parse_digit(c),
-            '(' => self.parse_check_paren(),
-            ' ' => self.grab_next_value(),
-            _ => panic!("Unrecognized char: '{}'", c)
+    pub fn grab_next_value(&mut self) -> Result<usize,String> {
+        match self.chars.next() {
+            Some(c @ '0'..='9') => Ok(parse_digit(c)),
+            Some('(') => self.parse_check_paren(),
+            Some(' ') => self.grab_next_value(),
+            Some(c) => Err(format!("Unrecognized char: '{}'", c)),
+            None => Err("Unexpected end of expression".to_string())
         }
     }
 
-    fn parse_check_paren(&mut self) -> usize {
-        let result = self.eval();
-        let next = self.chars.next().unwrap();
-        assert_eq!(next, ')');
-        result
+    fn parse_check_paren(&mut self) -> Result<usize,String> {
+        let result = self.eval()?;
+        match self.chars.next() {
+            Some(')') => Ok(result),
+            _ => Err("Missing ')'".to_string())
+        }
     }
 }
 
@@ -78,6 +99,7 @@ fn parse_digit(digit: char) -> usize {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn test_eval_1() {
@@ -89,7 +111,7 @@ mod tests {
             ("5 * 9 * (7 * 3 * 3 + 9 * 3 + (8 + 6 * 4))", 12240),
             ("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2", 13632)
         ] {
-            assert_eq!(puzzle_1(line).eval(), *target);
+            assert_eq!(puzzle_1(line).eval().unwrap(), *target);
         }
     }
 
@@ -112,7 +134,25 @@ mod tests {
             ("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4", 11670),
             ("((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2", 23340)
         ] {
-            assert_eq!(puzzle_2(line).eval(), *target);
+            assert_eq!(puzzle_2(line).eval().unwrap(), *target);
+        }
+    }
+
+    #[test]
+    fn test_malformed() {
+        for (line, error) in [
+            ("1 + x", "Unrecognized char: 'x'"),
+            ("1 +", "Unexpected end of expression"),
+            ("", "Unexpected end of expression"),
+            ("(1 + 2", "Missing ')'"),
+            ("1 + 2)", "Unexpected ')'"),
+            ("1 2", "Expected an operator, found '2'")
+        ].iter() {
+            assert_eq!(puzzle_1(line).eval_all().unwrap_err(), *error);
+            assert_eq!(puzzle_2(line).eval_all().unwrap_err(), *error);
         }
+        let input = InputFile::from_reader("1 + 2\n3 * (4 + \n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(),
+                   "day 18, line 2: Unexpected end of expression in `3 * (4 + `");
     }
-}
\ No newline at end of file
+}
@@ -1,51 +1,68 @@
 use std::collections::{BTreeMap, BTreeSet};
 use smallvec::SmallVec;
-use std::{io, fmt};
-use advent_code_lib::all_lines;
+use std::fmt;
 use std::collections::btree_map::Keys;
 use std::fmt::Display;
 use smallvec::alloc::fmt::Formatter;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 19;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     Ok(Rules::puzzle1(filename)?.to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     Ok(Rules::puzzle2(filename)?.to_string())
 }
 
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(19, 1, solve_1);
+    registry.add(19, 2, solve_2);
+}
+
 #[derive(Clone,Debug)]
 enum Rule {
     Char(char), Subrules(SmallVec<[usize; 3]>), Alt(Box<Rule>,Box<Rule>)
 }
 
-struct Rules {
+pub struct Rules {
     rules: BTreeMap<usize,Rule>
 }
 
-fn decode_option(chars: &str) -> Rule {
+fn decode_option(chars: &str) -> Result<Rule,String> {
     let chars = chars.trim();
-    let chars_bytes = chars.as_bytes();
-    if chars_bytes[0] == '"' as u8 {
-        Rule::Char(chars_bytes[1] as char)
+    if chars.starts_with('"') {
+        let quoted: Vec<char> = chars.chars().collect();
+        match quoted.as_slice() {
+            ['"', c, '"'] => Ok(Rule::Char(*c)),
+            _ => Err(format!("expected a single quoted character, found `{}`", chars))
+        }
+    } else if chars.is_empty() {
+        Err("empty rule option".to_string())
     } else {
-        Rule::Subrules(chars.split_whitespace().map(|s| s.parse::<usize>().unwrap()).collect())
+        Ok(Rule::Subrules(chars.split_whitespace().map(|s| parse_num(s)).collect::<Result<_,String>>()?))
     }
 }
 
-fn rule_line(line: &str) -> (usize, Rule) {
-    let mut colon = line.split(':');
-    let index = colon.next().unwrap().parse::<usize>().unwrap();
-    let mut options = colon.next().unwrap().split('|');
-    let option1 = decode_option(options.next().unwrap());
-    (index, if let Some(two) = options.next() {
-        Rule::Alt(Box::from(option1), Box::from(decode_option(two)))
-    } else {
-        option1
-    })
+fn rule_line(line: &str) -> Result<(usize, Rule),String> {
+    let mut colon = line.splitn(2, ':');
+    let (index, options) = match (colon.next(), colon.next()) {
+        (Some(index), Some(options)) => (parse_num(index.trim())?, options),
+        _ => return Err("expected `<number>: <rule>`".to_string())
+    };
+    let mut options = options.split('|');
+    let option1 = decode_option(options.next().unwrap())?;
+    Ok((index, match (options.next(), options.next()) {
+        (None, _) => option1,
+        (Some(two), None) => Rule::Alt(Box::from(option1), Box::from(decode_option(two)?)),
+        (Some(_), Some(_)) => return Err("at most two alternatives are supported".to_string())
+    }))
 }
 
-fn puzzle_2_rule_line(line: &str) -> (usize, Rule) {
+fn puzzle_2_rule_line(line: &str) -> Result<(usize, Rule),String> {
     rule_line(if line == "8: 42" {
         "8: 42 | 42 8"
     } else if line == "11: 42 31" {
@@ -56,30 +73,42 @@ fn puzzle_2_rule_line(line: &str) -> (usize, Rule) {
 }
 
 impl Rules {
-    fn from<F:Fn(&str)->(usize,Rule)>(filename: &str, rule_liner: F) -> io::Result<(Rules, impl Iterator<Item=String>)> {
-        let mut lines = all_lines(filename)?;
-        let rules = Rules {rules: lines.by_ref()
-            .take_while(|line| line.len() > 0)
-            .map(|line| rule_liner(line.as_str()))
-            .collect()};
-        Ok((rules, lines))
+    fn from<F:Fn(&str)->Result<(usize,Rule),String>>(filename: &str, rule_liner: F) -> AdventResult<(Rules, impl Iterator<Item=(usize,String)>)> {
+        let mut lines = numbered_lines(filename)?;
+        let mut rules = BTreeMap::new();
+        for (line_num, line) in lines.by_ref().take_while(|(_, line)| !line.is_empty()) {
+            let (index, rule) = rule_liner(line.as_str()).at_line(DAY, line_num, line.as_str())?;
+            rules.insert(index, rule);
+        }
+        if !rules.contains_key(&0) {
+            return Err(AdventError::bad_input(DAY, "no rule 0"));
+        }
+        Ok((Rules {rules}, lines))
     }
 
-    fn all_rule_nums(&self) -> Keys<usize,Rule> {
-        self.rules.keys()
+    // The rules and the messages to check against them; puzzle 2 makes rules 8 and 11 loop.
+    pub fn from_file(filename: &str, looping: bool) -> AdventResult<(Rules, Vec<String>)> {
+        let rule_liner = if looping {puzzle_2_rule_line} else {rule_line};
+        let (rules, lines) = Rules::from(filename, rule_liner)?;
+        Ok((rules, lines.map(|(_, line)| line).collect()))
     }
 
-    fn puzzle<F:Fn(&str)->(usize,Rule)>(filename: &str, rule_liner: F) -> io::Result<usize> {
-        let (rules, lines) = Rules::from(filename, rule_liner)?;
-        Ok(lines.filter(|line| ParseTable::matches(&rules, line.as_str())).count())
+    pub fn count_matches(&self, messages: &[String]) -> usize {
+        messages.iter().filter(|message| ParseTable::matches(self, message.as_str())).count()
+    }
+
+    fn all_rule_nums(&self) -> Keys<usize,Rule> {
+        self.rules.keys()
     }
 
-    fn puzzle1(filename: &str) -> io::Result<usize> {
-        Rules::puzzle(filename, rule_line)
+    fn puzzle1(filename: &str) -> AdventResult<usize> {
+        let (rules, messages) = Rules::from_file(filename, false)?;
+        Ok(rules.count_matches(&messages))
     }
 
-    fn puzzle2(filename: &str) -> io::Result<usize> {
-        Rules::puzzle(filename, puzzle_2_rule_line)
+    fn puzzle2(filename: &str) -> AdventResult<usize> {
+        let (rules, messages) = Rules::from_file(filename, true)?;
+        Ok(rules.count_matches(&messages))
     }
 
     fn rule(&self, ri: usize) -> &Rule {
@@ -138,7 +167,7 @@ impl ParseTable {
     }
 
     fn full_match(&self) -> bool {
-        self.matches_rule_at(0, 0)
+        !self.status.is_empty() && self.matches_rule_at(0, 0)
     }
 
     fn matches(rules: &Rules, line: &str) -> bool {
@@ -213,32 +242,33 @@ impl ParseTable {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn test1_1() {
-        assert_eq!(Rules::puzzle1("in/day19_ex.txt").unwrap(), 2);
+        assert_eq!(Rules::puzzle1("fixtures/day19_ex.txt").unwrap(), 2);
     }
 
     #[test]
     fn test1_2() {
-        assert_eq!(Rules::puzzle1("in/day19_ex2.txt").unwrap(), 3);
+        assert_eq!(Rules::puzzle1("fixtures/day19_ex2.txt").unwrap(), 3);
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(Rules::puzzle2("in/day19_ex2.txt").unwrap(), 12);
+        assert_eq!(Rules::puzzle2("fixtures/day19_ex2.txt").unwrap(), 12);
     }
 
     #[test]
     fn test_row_1() {
-        let rules = Rules::from("in/day19_ex.txt", rule_line).unwrap().0;
+        let rules = Rules::from("fixtures/day19_ex.txt", rule_line).unwrap().0;
         let table = ParseTable::from(&rules, "ababbb");
         assert!(table.full_match());
     }
 
     #[test]
     fn test_rows_2() {
-        let (rules,_) = Rules::from("in/day19_ex2.txt", puzzle_2_rule_line).unwrap();
+        let (rules,_) = Rules::from("fixtures/day19_ex2.txt", puzzle_2_rule_line).unwrap();
         [
             ("abbbbbabbbaaaababbaabbbbabababbbabbbbbbabaaaa", false),
             ("bbabbbbaabaabba", true),
@@ -270,4 +300,25 @@ mod tests {
     fn test_or() {
         assert_eq!(or(Status::Yes(btreeset! {2}), Status::No), Status::Yes(btreeset! {2}));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bad_rules() {
+        for (line, error) in [
+            ("x: 1 2", "`x` is not a number"),
+            ("0 1 2", "expected `<number>: <rule>`"),
+            ("0: 1 b", "`b` is not a number"),
+            ("0: \"ab\"", "expected a single quoted character, found `\"ab\"`"),
+            ("0: 1 |", "empty rule option"),
+            ("0: 1 | 2 | 3", "at most two alternatives are supported")
+        ].iter() {
+            assert_eq!(rule_line(line).unwrap_err(), *error);
+        }
+        let input = InputFile::from_reader("0: 1\n1: \"a\nb\n\na\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(),
+                   "day 19, line 2: expected a single quoted character, found `\"a` in `1: \"a`");
+        let input = InputFile::from_reader("1: \"a\"\n\na\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), "day 19: bad input: no rule 0");
+        let input = InputFile::from_reader("0: \"a\"\n\na\n\nb\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap(), "1");
+    }
+}
@@ -1,67 +1,287 @@
-use std::io;
-use advent_code_lib::pass_counter;
+use std::fmt;
+use std::fmt::{Display, Formatter};
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1() -> io::Result<String> {
-    pass_counter("in/day2.txt", line_valid_password_1)
+const DAY: usize = 2;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    Ok(count_valid(filename, &CountInRange)?.to_string())
+}
+
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    Ok(count_valid(filename, &ExactlyOnePosition)?.to_string())
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(2, 1, solve_1);
+    registry.add(2, 2, solve_2);
+}
+
+pub fn count_valid(filename: &str, policy: &dyn PasswordPolicy) -> AdventResult<usize> {
+    Ok(count_valid_lines(&policy_lines(filename)?, policy))
+}
+
+pub fn count_valid_lines(lines: &[(usize,PolicyLine)], policy: &dyn PasswordPolicy) -> usize {
+    lines.iter().filter(|(_, line)| policy.valid(line)).count()
 }
 
-pub fn solve_2() -> io::Result<String> {
-    pass_counter("in/day2.txt", line_valid_password_2)
+pub fn policy_lines(filename: &str) -> AdventResult<Vec<(usize,PolicyLine)>> {
+    let mut lines = Vec::new();
+    for (line_num, line) in numbered_lines(filename)? {
+        lines.push((line_num, PolicyLine::parse(line.as_str()).at_line(DAY, line_num, line.as_str())?));
+    }
+    Ok(lines)
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct PolicyLine {
+    pub lo: usize,
+    pub hi: usize,
+    pub letter: char,
+    pub password: String
+}
+
+impl PolicyLine {
+    pub fn parse(line: &str) -> Result<Self,String> {
+        let spaced: Vec<&str> = line.split_whitespace().collect();
+        if spaced.len() != 3 || !spaced[1].ends_with(':') {
+            return Err("expected `lo-hi letter: password`".to_string());
+        }
+        let ranged: Vec<&str> = spaced[0].split('-').collect();
+        if ranged.len() != 2 {
+            return Err(format!("expected a range like `1-3`, found `{}`", spaced[0]));
+        }
+        let letter = match spaced[1].chars().collect::<Vec<char>>().as_slice() {
+            [letter, ':'] => *letter,
+            _ => return Err(format!("expected a single letter before `:`, found `{}`", spaced[1]))
+        };
+        Ok(PolicyLine {lo: parse_num(ranged[0])?, hi: parse_num(ranged[1])?, letter, password: spaced[2].to_string()})
+    }
+
+    pub fn count(&self) -> usize {
+        self.password.matches(self.letter).count()
+    }
+
+    // Positions are 1-based; a position outside the password never holds the letter.
+    pub fn letter_at(&self, position: usize) -> bool {
+        self.in_bounds(position) && self.password.chars().nth(position - 1) == Some(self.letter)
+    }
+
+    pub fn in_bounds(&self, position: usize) -> bool {
+        position > 0 && position <= self.password.chars().count()
+    }
+
+    pub fn longest_run(&self) -> usize {
+        let mut longest = 0;
+        let mut run = 0;
+        for c in self.password.chars() {
+            run = if c == self.letter {run + 1} else {0};
+            longest = longest.max(run);
+        }
+        longest
+    }
 }
 
-fn line_valid_password_1(line: &str) -> bool {
-    let (lo, hi, letter, password) = parse_password_line(line);
-    let count = password.matches(letter).count();
-    count >= lo && count <= hi
+impl Display for PolicyLine {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{} {}: {}", self.lo, self.hi, self.letter, self.password)
+    }
 }
 
-fn in_bounds(i: usize, password: &[u8]) -> bool {
-    i > 0 && i <= password.len()
+pub trait PasswordPolicy {
+    fn name(&self) -> &'static str;
+    fn valid(&self, line: &PolicyLine) -> bool;
 }
 
-fn at(i: usize, password: &[u8], letter: char) -> bool {
-    password[i - 1] == letter as u8
+// The letter appears between lo and hi times.
+pub struct CountInRange;
+
+impl PasswordPolicy for CountInRange {
+    fn name(&self) -> &'static str {"count"}
+
+    fn valid(&self, line: &PolicyLine) -> bool {
+        let count = line.count();
+        count >= line.lo && count <= line.hi
+    }
 }
 
-fn line_valid_password_2(line: &str) -> bool {
-    let (lo, hi, letter, password) = parse_password_line(line);
-    let password = password.as_bytes();
-    in_bounds(lo, password) && in_bounds(hi, password) &&
-        at(lo, password, letter) != at(hi, password, letter)
+// Both positions lo and hi are in the password, and exactly one holds the letter.
+pub struct ExactlyOnePosition;
+
+impl PasswordPolicy for ExactlyOnePosition {
+    fn name(&self) -> &'static str {"exactly-one"}
+
+    fn valid(&self, line: &PolicyLine) -> bool {
+        line.in_bounds(line.lo) && line.in_bounds(line.hi) && line.letter_at(line.lo) != line.letter_at(line.hi)
+    }
 }
 
-fn parse_password_line(line: &str) -> (usize,usize,char,&str) {
-    let spaced: Vec<&str> = line.split_whitespace().collect();
-    let ranged: Vec<usize> = spaced[0].split('-').map(|v| v.parse::<usize>().unwrap()).collect();
-    (ranged[0], ranged[1], spaced[1].chars().next().unwrap(), spaced[2])
+// The letter is at position lo, position hi, or both.
+pub struct AtLeastOnePosition;
+
+impl PasswordPolicy for AtLeastOnePosition {
+    fn name(&self) -> &'static str {"at-least-one"}
+
+    fn valid(&self, line: &PolicyLine) -> bool {
+        line.letter_at(line.lo) || line.letter_at(line.hi)
+    }
+}
+
+// No run of consecutive copies of the letter is longer than hi.
+pub struct MaxRun;
+
+impl PasswordPolicy for MaxRun {
+    fn name(&self) -> &'static str {"max-run"}
+
+    fn valid(&self, line: &PolicyLine) -> bool {
+        line.longest_run() <= line.hi
+    }
+}
+
+// The letter does not appear at all.
+pub struct ForbiddenLetter;
+
+impl PasswordPolicy for ForbiddenLetter {
+    fn name(&self) -> &'static str {"forbidden"}
+
+    fn valid(&self, line: &PolicyLine) -> bool {
+        line.count() == 0
+    }
+}
+
+pub fn all_policies() -> Vec<Box<dyn PasswordPolicy>> {
+    vec![Box::new(CountInRange), Box::new(ExactlyOnePosition), Box::new(AtLeastOnePosition),
+         Box::new(MaxRun), Box::new(ForbiddenLetter)]
+}
+
+pub fn policy_named(name: &str) -> Option<Box<dyn PasswordPolicy>> {
+    all_policies().into_iter().find(|p| p.name() == name)
+}
+
+// Accepts `all` or a comma-separated list of policy names.
+pub fn policies_named(names: &str) -> Result<Vec<Box<dyn PasswordPolicy>>,String> {
+    if names == "all" {
+        return Ok(all_policies());
+    }
+    names.split(',')
+        .map(|name| policy_named(name).ok_or(format!("Unrecognized policy `{}`", name)))
+        .collect()
+}
+
+pub fn policy_report(filename: &str, policies: &[Box<dyn PasswordPolicy>]) -> AdventResult<String> {
+    let lines = policy_lines(filename)?;
+    let mut report = String::new();
+    for policy in policies.iter() {
+        let valid = lines.iter().filter(|(_, line)| policy.valid(line)).count();
+        report.push_str(format!("{}: {} of {} valid\n", policy.name(), valid, lines.len()).as_str());
+    }
+    for (line_num, line) in lines.iter() {
+        let failed: Vec<&str> = policies.iter()
+            .filter(|p| !p.valid(line))
+            .map(|p| p.name())
+            .collect();
+        if !failed.is_empty() {
+            report.push_str(format!("line {} `{}`: {}\n", line_num, line, failed.join(", ")).as_str());
+        }
+    }
+    Ok(report)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::personal_input;
+    use crate::input::InputFile;
+
+    fn valid(policy: &dyn PasswordPolicy, line: &str) -> bool {
+        policy.valid(&PolicyLine::parse(line).unwrap())
+    }
 
     #[test]
     fn test_one_line_1() {
-        assert!(line_valid_password_1("1-3 a: abcde"));
-        assert!(!line_valid_password_1("1-3 b: cdefg"));
-        assert!(line_valid_password_1("2-9 c: ccccccccc"));
-        assert!(!line_valid_password_1("2-9 c: cccccccccc"));
+        assert!(valid(&CountInRange, "1-3 a: abcde"));
+        assert!(!valid(&CountInRange, "1-3 b: cdefg"));
+        assert!(valid(&CountInRange, "2-9 c: ccccccccc"));
+        assert!(!valid(&CountInRange, "2-9 c: cccccccccc"));
+    }
+
+    #[test]
+    fn test_example() {
+        assert_eq!(solve_1("fixtures/day2_ex.txt").unwrap(), "2");
+        assert_eq!(solve_2("fixtures/day2_ex.txt").unwrap(), "1");
     }
 
     #[test]
     fn test_part_1() {
-        assert_eq!(solve_1().unwrap(), "550");
+        if let Some(input) = personal_input(2) {
+            assert_eq!(solve_1(input.as_str()).unwrap(), "550");
+        }
     }
 
     #[test]
     fn test_one_line_2() {
-        assert!(line_valid_password_2("1-3 a: abcde"));
-        assert!(!line_valid_password_2("1-3 b: cdefg"));
-        assert!(!line_valid_password_2("2-9 c: ccccccccc"));
+        assert!(valid(&ExactlyOnePosition, "1-3 a: abcde"));
+        assert!(!valid(&ExactlyOnePosition, "1-3 b: cdefg"));
+        assert!(!valid(&ExactlyOnePosition, "2-9 c: ccccccccc"));
+        assert!(!valid(&ExactlyOnePosition, "1-9 a: abc"));
+    }
+
+    #[test]
+    fn test_other_policies() {
+        assert!(valid(&AtLeastOnePosition, "1-3 a: abcde"));
+        assert!(valid(&AtLeastOnePosition, "2-9 c: ccccccccc"));
+        assert!(!valid(&AtLeastOnePosition, "1-3 b: cdefg"));
+        assert!(valid(&AtLeastOnePosition, "1-9 a: abc"));
+        assert!(valid(&MaxRun, "1-2 a: aabaa"));
+        assert!(!valid(&MaxRun, "1-2 a: baaab"));
+        assert_eq!(PolicyLine::parse("1-2 a: baaaba").unwrap().longest_run(), 3);
+        assert!(valid(&ForbiddenLetter, "1-3 b: acdef"));
+        assert!(!valid(&ForbiddenLetter, "1-3 a: abcde"));
+    }
+
+    #[test]
+    fn test_policies_named() {
+        let names: Vec<&str> = all_policies().iter().map(|p| p.name()).collect();
+        assert_eq!(names, vec!["count", "exactly-one", "at-least-one", "max-run", "forbidden"]);
+        assert_eq!(policy_named("max-run").unwrap().name(), "max-run");
+        assert!(policy_named("nope").is_none());
+        assert_eq!(policies_named("all").unwrap().len(), 5);
+        assert_eq!(policies_named("count,forbidden").unwrap().iter().map(|p| p.name()).collect::<Vec<_>>(), vec!["count", "forbidden"]);
+        assert_eq!(policies_named("count,nope").err().unwrap(), "Unrecognized policy `nope`");
+    }
+
+    #[test]
+    fn test_policy_report() {
+        let policies = policies_named("count,exactly-one,at-least-one").unwrap();
+        assert_eq!(policy_report("fixtures/day2_ex.txt", &policies).unwrap(), "\
+count: 2 of 3 valid
+exactly-one: 1 of 3 valid
+at-least-one: 2 of 3 valid
+line 2 `1-3 b: cdefg`: count, exactly-one, at-least-one
+line 3 `2-9 c: ccccccccc`: exactly-one
+");
+    }
+
+    #[test]
+    fn test_malformed_lines() {
+        for (line, reason) in [("1-3 a abcde", "expected `lo-hi letter: password`"),
+            ("13 a: abcde", "expected a range like `1-3`, found `13`"),
+            ("1-x a: abcde", "`x` is not a number"),
+            ("1-3 ab: abcde", "expected a single letter before `:`, found `ab:`"),
+            ("1-3 : abcde", "expected a single letter before `:`, found `:`")].iter() {
+            assert_eq!(PolicyLine::parse(line).unwrap_err(), *reason);
+        }
+        let input = InputFile::from_reader("1-3 a: abcde\n1-3 b cdefg\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(),
+                   "day 2, line 2: expected `lo-hi letter: password` in `1-3 b cdefg`");
     }
 
     #[test]
     fn test_part_2() {
-        assert_eq!(solve_2().unwrap(), "634");
+        if let Some(input) = personal_input(2) {
+            assert_eq!(solve_2(input.as_str()).unwrap(), "634");
+        }
     }
 }
@@ -1,25 +1,38 @@
 use std::fmt::Display;
 use smallvec::SmallVec;
 use smallvec::alloc::fmt::Formatter;
-use std::{fmt, io};
+use std::fmt;
 use std::collections::{BTreeMap, BTreeSet};
-use advent_code_lib::{all_lines, ManhattanDir, Position, DirType};
+use advent_code_lib::{ManhattanDir, Position, DirType};
 use enum_iterator::IntoEnumIterator;
 use itertools::Itertools;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 20;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     Ok(PuzzlePieces::from(filename)?.corner_product().to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
-    let (count, monsters) = find_monsters(filename)?;
-    println!("{}", monsters);
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    let (count, monsters) = find_monsters(&PuzzlePieces::from(filename)?)?;
+    eprintln!("{}", monsters);
     Ok(count.to_string())
 }
 
-fn find_monsters(filename: &str) -> io::Result<(usize, Tile)> {
-    let pp = PuzzlePieces::from(filename)?;
-    Ok(Layout::from(&pp).image(&pp).all_variants().iter()
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(20, 1, solve_1);
+    registry.add(20, 2, solve_2);
+}
+
+// The number of `#` left outside sea monsters, with the image oriented to show them.
+pub fn find_monsters(pp: &PuzzlePieces) -> AdventResult<(usize, Tile)> {
+    if pp.corner_ids().is_empty() {
+        return Err(AdventError::no_solution(DAY, "no corner tiles"));
+    }
+    Ok(Layout::from(pp).image(pp).all_variants().iter()
         .map(|tile| {
             let mut tile = tile.clone();
             tile.show_monsters();
@@ -29,7 +42,7 @@ fn find_monsters(filename: &str) -> io::Result<(usize, Tile)> {
 }
 
 #[derive(Clone,Debug,Eq,PartialEq,Ord,PartialOrd)]
-struct Tile {
+pub struct Tile {
     id: i64,
     pixels: SmallVec<[SmallVec<[char; 10]>; 10]>
 }
@@ -45,15 +58,18 @@ impl Display for Tile {
 }
 
 impl Tile {
-    fn from<I:Iterator<Item=String>>(lines: &mut I) -> Option<Self> {
-        lines.next().map(|header| {
-            let id = header.split_whitespace().skip(1).next().unwrap().split(':').next().unwrap().parse::<i64>().unwrap();
-            let pixels = lines
-                .take_while(|line| line.len() > 0)
-                .map(|line| line.chars().collect())
-                .collect();
-            Tile {id, pixels}
-        })
+    // The rows of `#` and `.` after a `Tile <id>:` header, ending at a blank line.
+    fn from<I:Iterator<Item=(usize,String)>>(line_num: usize, header: &str, lines: &mut I) -> AdventResult<Self> {
+        let id = parse_header(header).at_line(DAY, line_num, header)?;
+        let mut pixels: SmallVec<[SmallVec<[char; 10]>; 10]> = SmallVec::new();
+        for (line_num, line) in lines.take_while(|(_, line)| !line.is_empty()) {
+            let row = parse_row(line.as_str(), pixels.first().map(|r| r.len())).at_line(DAY, line_num, line.as_str())?;
+            pixels.push(row);
+        }
+        if pixels.is_empty() || pixels.len() != pixels[0].len() {
+            return Err(AdventError::parse(DAY, line_num, header, "tiles must be square"));
+        }
+        Ok(Tile {id, pixels})
     }
 
     fn all_variants(&self) -> Vec<Tile> {
@@ -68,8 +84,8 @@ impl Tile {
     }
 
     fn sea_monster() -> Self {
-        let mut monster_lines = "Tile: 13\n                  # \n#    ##    ##    ###\n #  #  #  #  #  #   ".lines().map(|s| s.to_string());
-        Tile::from(&mut monster_lines).unwrap()
+        let monster_lines = "                  # \n#    ##    ##    ###\n #  #  #  #  #  #   ".lines();
+        Tile {id: 13, pixels: monster_lines.map(|line| line.chars().collect()).collect()}
     }
 
     fn show_monsters(&mut self) {
@@ -170,28 +186,50 @@ impl Tile {
     }
 }
 
+fn parse_header(header: &str) -> Result<i64,String> {
+    header.trim().strip_prefix("Tile ")
+        .and_then(|rest| rest.strip_suffix(':'))
+        .ok_or("expected `Tile <id>:`".to_string())
+        .and_then(|id| parse_num(id))
+}
+
+fn parse_row(line: &str, width: Option<usize>) -> Result<SmallVec<[char; 10]>,String> {
+    if let Some(c) = line.chars().find(|c| *c != '#' && *c != '.') {
+        Err(format!("Unrecognized pixel '{}'", c))
+    } else if width.map_or(false, |width| width != line.len()) {
+        Err(format!("expected {} pixels, found {}", width.unwrap(), line.len()))
+    } else {
+        Ok(line.chars().collect())
+    }
+}
+
 #[derive(Debug,Clone)]
-struct PuzzlePieces {
+pub struct PuzzlePieces {
     tiles: BTreeMap<i64,Tile>,
     tile_height: usize,
     tile_width: usize
 }
 
 impl PuzzlePieces {
-    fn from(filename: &str) -> io::Result<Self> {
+    pub fn from(filename: &str) -> AdventResult<Self> {
         let mut tiles = BTreeMap::new();
         let mut tile_width = 0;
         let mut tile_height = 0;
-        let mut lines = all_lines(filename)?;
-        loop {
-            match Tile::from(&mut lines) {
-                None => break,
-                Some(tile) => {
-                    tile_width = tile.width();
-                    tile_height = tile.height();
-                    tiles.insert(tile.id, tile);
-                }
+        let mut lines = numbered_lines(filename)?;
+        while let Some((line_num, header)) = lines.find(|(_, line)| !line.trim().is_empty()) {
+            let tile = Tile::from(line_num, header.as_str(), &mut lines)?;
+            if !tiles.is_empty() && (tile.width(), tile.height()) != (tile_width, tile_height) {
+                return Err(AdventError::parse(DAY, line_num, header.as_str(), "tile differs in size from the others"));
+            }
+            if tiles.contains_key(&tile.id) {
+                return Err(AdventError::parse(DAY, line_num, header.as_str(), "tile appears twice"));
             }
+            tile_width = tile.width();
+            tile_height = tile.height();
+            tiles.insert(tile.id, tile);
+        }
+        if tiles.is_empty() {
+            return Err(AdventError::bad_input(DAY, "no tiles"));
         }
         Ok(PuzzlePieces { tiles, tile_height, tile_width })
     }
@@ -208,7 +246,7 @@ impl PuzzlePieces {
         self.ids_with_friends(2)
     }
 
-    fn corner_product(&self) -> i64 {
+    pub fn corner_product(&self) -> i64 {
         self.corner_ids().iter().product()
     }
 }
@@ -414,11 +452,12 @@ impl Display for Layout {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::{personal_input, InputFile};
     use advent_code_lib::ManhattanDir;
 
     #[test]
     fn load_ex() {
-        let pp = PuzzlePieces::from("in/day20_ex.txt").unwrap();
+        let pp = PuzzlePieces::from("fixtures/day20_ex.txt").unwrap();
         let nums = [2311, 1951, 1171, 1427, 1489, 2473, 2971, 2729, 3079];
         assert_eq!(pp.tiles.len(), nums.len());
         assert!(nums.iter().all(|num| pp.tiles.contains_key(num)));
@@ -429,7 +468,9 @@ mod tests {
     }
 
     fn str_to_tile(s: &str) -> Tile {
-        Tile::from(&mut s.lines().map(|s| s.to_string())).unwrap()
+        let mut lines = s.lines().map(|s| s.to_string()).enumerate();
+        let (line_num, header) = lines.next().unwrap();
+        Tile::from(line_num, header.as_str(), &mut lines).unwrap()
     }
 
     #[test]
@@ -474,28 +515,30 @@ mod tests {
 
     #[test]
     fn puzzle1() {
-        assert_eq!(solve_1("in/day20_ex.txt").unwrap(), "20899048083289");
+        assert_eq!(solve_1("fixtures/day20_ex.txt").unwrap(), "20899048083289");
     }
 
     #[test]
     fn max_2_ids_per_edge() {
-        let constraints = Constraints::new(&PuzzlePieces::from("in/day20.txt").unwrap());
-        let mut counts = BTreeSet::new();
-        for (_, id_set) in constraints.edges2ids() {
-            counts.insert(id_set.len());
+        if let Some(input) = personal_input(20) {
+            let constraints = Constraints::new(&PuzzlePieces::from(input.as_str()).unwrap());
+            let mut counts = BTreeSet::new();
+            for (_, id_set) in constraints.edges2ids() {
+                counts.insert(id_set.len());
+            }
+            assert_eq!(counts, btreeset! {1, 2});
         }
-        assert_eq!(counts, btreeset! {1, 2});
     }
 
     #[test]
     fn example_corners() {
-        let pp = PuzzlePieces::from("in/day20_ex.txt").unwrap();
+        let pp = PuzzlePieces::from("fixtures/day20_ex.txt").unwrap();
         assert_eq!(pp.corner_ids(), btreeset! {1171, 1951, 2971, 3079});
     }
 
     #[test]
     fn layout_example() {
-        let pp = PuzzlePieces::from("in/day20_ex.txt").unwrap();
+        let pp = PuzzlePieces::from("fixtures/day20_ex.txt").unwrap();
         let layout = Layout::from(&pp);
         assert_eq!(layout.tiles.len(), 9);
         assert_eq!(format!("{}", layout), "3079 2311 1951 \n2473 1427 2729 \n1171 1489 2971 \n");
@@ -529,15 +572,17 @@ mod tests {
 
     #[test]
     fn layout_real() {
-        let pp = PuzzlePieces::from("in/day20.txt").unwrap();
-        let layout = Layout::from(&pp);
-        assert_eq!(layout.tiles.len(), 144);
-        assert_eq!(format!("{}", layout), "3389 3169 2591 1511 1901 2467 1777 1667 2797 3449 2861 1657 \n3461 2179 3391 1607 1487 1297 2609 3923 3931 3697 3559 1049 \n1327 3659 3011 1217 1423 2503 1303 2111 1061 2441 2897 2389 \n3719 3253 3491 2251 2399 2789 2543 3413 3797 1051 1163 2381 \n1877 3257 1549 2887 1949 1447 3821 1619 1483 1319 1571 3947 \n2801 1109 2099 1231 1381 1367 2137 2677 2311 1579 3323 2729 \n3761 3319 2833 3187 2663 1697 3889 1583 3023 1489 1741 3583 \n3853 2213 2351 3581 1409 1427 3733 2741 2557 3271 1693 1973 \n1009 1087 1559 2011 1997 3299 1789 3301 3593 3163 1093 3767 \n2113 2879 3229 3313 1277 3863 3623 2837 1747 3191 1123 1709 \n2963 2767 3617 1907 3331 2939 3527 2081 1279 1091 2339 1021 \n1621 3793 1879 3709 1181 3881 2593 1801 1307 3541 3727 3547 \n");
+        if let Some(input) = personal_input(20) {
+            let pp = PuzzlePieces::from(input.as_str()).unwrap();
+            let layout = Layout::from(&pp);
+            assert_eq!(layout.tiles.len(), 144);
+            assert_eq!(format!("{}", layout), "3389 3169 2591 1511 1901 2467 1777 1667 2797 3449 2861 1657 \n3461 2179 3391 1607 1487 1297 2609 3923 3931 3697 3559 1049 \n1327 3659 3011 1217 1423 2503 1303 2111 1061 2441 2897 2389 \n3719 3253 3491 2251 2399 2789 2543 3413 3797 1051 1163 2381 \n1877 3257 1549 2887 1949 1447 3821 1619 1483 1319 1571 3947 \n2801 1109 2099 1231 1381 1367 2137 2677 2311 1579 3323 2729 \n3761 3319 2833 3187 2663 1697 3889 1583 3023 1489 1741 3583 \n3853 2213 2351 3581 1409 1427 3733 2741 2557 3271 1693 1973 \n1009 1087 1559 2011 1997 3299 1789 3301 3593 3163 1093 3767 \n2113 2879 3229 3313 1277 3863 3623 2837 1747 3191 1123 1709 \n2963 2767 3617 1907 3331 2939 3527 2081 1279 1091 2339 1021 \n1621 3793 1879 3709 1181 3881 2593 1801 1307 3541 3727 3547 \n");
+        }
     }
 
     #[test]
     fn monster() {
-        let (count, monsters) = find_monsters("in/day20_ex.txt").unwrap();
+        let (count, monsters) = find_monsters(&PuzzlePieces::from("fixtures/day20_ex.txt").unwrap()).unwrap();
         let found = "Tile 0:
 .####...#####..#...###..
 #####..#..#.#.####..#.#.
@@ -567,4 +612,23 @@ mod tests {
         assert_eq!(format!("{}", monsters), found);
         assert_eq!(count, 273);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_bad_tiles() {
+        for (text, error) in [
+            ("Tile x:\n#.\n.#\n", "day 20, line 1: `x` is not a number in `Tile x:`"),
+            ("Tle 12:\n#.\n.#\n", "day 20, line 1: expected `Tile <id>:` in `Tle 12:`"),
+            ("Tile 1:\n#.\n.#\n\nTile 2:\n#.\n.o\n", "day 20, line 7: Unrecognized pixel 'o' in `.o`"),
+            ("Tile 1:\n#.\n.#.\n", "day 20, line 3: expected 2 pixels, found 3 in `.#.`"),
+            ("Tile 1:\n#.\n", "day 20, line 1: tiles must be square in `Tile 1:`"),
+            ("Tile 1:\n#.\n.#\n\nTile 1:\n#.\n.#\n", "day 20, line 5: tile appears twice in `Tile 1:`"),
+            ("Tile 1:\n#.\n.#\n\nTile 2:\n#..\n.#.\n...\n", "day 20, line 5: tile differs in size from the others in `Tile 2:`"),
+            ("\n", "day 20: bad input: no tiles")
+        ].iter() {
+            let input = InputFile::from_reader(text.as_bytes()).unwrap();
+            assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), *error);
+        }
+        let input = InputFile::from_reader("Tile 1:\n#.\n.#\n".as_bytes()).unwrap();
+        assert_eq!(solve_2(input.filename()).unwrap_err().to_string(), "day 20: no solution: no corner tiles");
+    }
+}
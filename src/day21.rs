@@ -1,16 +1,24 @@
 use std::collections::{BTreeMap, BTreeSet};
 use histogram::Histogram;
-use std::io;
-use advent_code_lib::all_lines;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 21;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     Ok(Allergens::from(filename)?.allergen_free_counts().to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     Ok(Allergens::from(filename)?.canonical_dangerous_list())
 }
 
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(21, 1, solve_1);
+    registry.add(21, 2, solve_2);
+}
+
 #[derive(Debug)]
 struct AllergenCandidates {
     allergen2candidates: BTreeMap<String,BTreeSet<String>>,
@@ -30,13 +38,10 @@ impl AllergenCandidates {
         }
     }
 
-    fn single_candidate(&self) -> (String,String) {
-        for (candidate, set) in self.allergen2candidates.iter() {
-            if set.len() == 1 {
-                return (candidate.clone(), set.first().unwrap().clone())
-            }
-        }
-        panic!("This shouldn't happen")
+    fn single_candidate(&self) -> Option<(String,String)> {
+        self.allergen2candidates.iter()
+            .find(|(_, set)| set.len() == 1)
+            .map(|(candidate, set)| (candidate.clone(), set.first().unwrap().clone()))
     }
 
     fn purge_candidate(&mut self, candidate: &str) {
@@ -45,36 +50,41 @@ impl AllergenCandidates {
         }
     }
 
-    fn reduce(&mut self) {
+    fn reduce(&mut self) -> AdventResult<()> {
         while !self.allergen2candidates.is_empty() {
-            let (allergen, ingredient) = self.single_candidate();
+            let (allergen, ingredient) = self.single_candidate()
+                .ok_or_else(|| AdventError::no_solution(DAY, "no allergen narrows down to a single ingredient"))?;
             self.allergen2candidates.remove(allergen.as_str());
             self.purge_candidate(ingredient.as_str());
             self.allergen2ingredient.insert(allergen, ingredient);
         }
+        Ok(())
     }
 }
 
 #[derive(Debug)]
-struct Allergens {
+pub struct Allergens {
     allergen2ingredient: BTreeMap<String,String>,
     safe_ingredient_counts: BTreeMap<String,usize>
 }
 
 impl Allergens {
-    fn from(filename: &str) -> io::Result<Self> {
+    fn from(filename: &str) -> AdventResult<Self> {
+        Allergens::from_foods(&foods(filename)?)
+    }
+
+    pub fn from_foods(foods: &[(Vec<String>, Vec<String>)]) -> AdventResult<Self> {
         let mut allergen_search = AllergenCandidates::new();
         let mut ingredient_counter = Histogram::new();
-        all_lines(filename)?.for_each(|line| {
-            let (ingredients, allergens) = process_input_line(line);
-            for allergen in allergens {
-                allergen_search.add(allergen.as_str(), &ingredients);
+        for (ingredients, allergens) in foods.iter() {
+            for allergen in allergens.iter() {
+                allergen_search.add(allergen.as_str(), ingredients);
             }
-            for ingredient in ingredients {
-                ingredient_counter.bump(&ingredient);
+            for ingredient in ingredients.iter() {
+                ingredient_counter.bump(ingredient);
             }
-        });
-        allergen_search.reduce();
+        }
+        allergen_search.reduce()?;
         let unsafe_ingredients: BTreeSet<_> = allergen_search.allergen2ingredient.values().collect();
         let safe_ingredient_counts = ingredient_counter.iter()
             .filter(|(i, _)| !unsafe_ingredients.contains(i))
@@ -83,32 +93,55 @@ impl Allergens {
         Ok(Allergens {allergen2ingredient: allergen_search.allergen2ingredient, safe_ingredient_counts })
     }
 
-    fn allergen_free_counts(&self) -> usize {
+    pub fn allergen_free_counts(&self) -> usize {
         self.safe_ingredient_counts.iter().map(|(_, c)| c).sum()
     }
 
-    fn canonical_dangerous_list(&self) -> String {
+    pub fn canonical_dangerous_list(&self) -> String {
         let ingredients: Vec<_> = self.allergen2ingredient.iter().map(|(_, ingredient)| ingredient.clone()).collect();
         ingredients.join(",")
     }
 }
 
-fn process_input_line(line: String) -> (Vec<String>, Vec<String>) {
+// Each food's ingredients and the allergens it is known to contain.
+pub fn foods(filename: &str) -> AdventResult<Vec<(Vec<String>, Vec<String>)>> {
+    numbered_lines(filename)?
+        .map(|(line_num, line)| process_input_line(line.as_str()).at_line(DAY, line_num, line.as_str()))
+        .collect()
+}
+
+pub fn process_input_line(line: &str) -> Result<(Vec<String>, Vec<String>),String> {
     let mut parts1 = line.split(" (contains ");
     let ingredients = parts1.next().unwrap().split_whitespace().map(|s| s.to_string()).collect();
-    let allergens = parts1.next().unwrap().split(&[',', ' ', ')'][..]).filter(|s| s.len() > 0).map(|s| s.to_string()).collect();
-    (ingredients, allergens)
+    let allergens = parts1.next()
+        .and_then(|allergens| allergens.strip_suffix(')'))
+        .ok_or("expected `<ingredients> (contains <allergens>)`")?
+        .split(&[',', ' '][..]).filter(|s| !s.is_empty()).map(|s| s.to_string()).collect();
+    Ok((ingredients, allergens))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn test_parse() {
-        let allergens = Allergens::from("in/day21_ex.txt").unwrap();
+        let allergens = Allergens::from("fixtures/day21_ex.txt").unwrap();
         assert_eq!(allergens.allergen_free_counts(), 5);
         assert_eq!(allergens.canonical_dangerous_list(), "mxmxvkd,sqjhc,fvjkl");
         println!("{:?}", allergens);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_malformed() {
+        assert_eq!(process_input_line("mxmxvkd kfcds (contains dairy").unwrap_err(),
+                   "expected `<ingredients> (contains <allergens>)`");
+        let input = InputFile::from_reader("a b (contains dairy)\nc d\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(),
+                   "day 21, line 2: expected `<ingredients> (contains <allergens>)` in `c d`");
+        let input = InputFile::from_reader("a b (contains dairy)\nc d (contains dairy)\n".as_bytes()).unwrap();
+        assert_eq!(solve_2(input.filename()).unwrap_err().to_string(),
+                   "day 21: no solution: no allergen narrows down to a single ingredient");
+    }
+}
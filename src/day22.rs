@@ -1,43 +1,50 @@
 use std::collections::{VecDeque, BTreeSet};
-use std::io;
-use advent_code_lib::all_lines;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 22;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     solve(filename, false)
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     solve(filename, true)
 }
 
-fn solve(filename: &str, recursive: bool) -> io::Result<String> {
-    let mut game = Game::from(filename, recursive)?;
-    let (_, score) = game.play_to_end();
-    Ok(score.to_string())
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(22, 1, solve_1);
+    registry.add(22, 2, solve_2);
+}
+
+fn solve(filename: &str, recursive: bool) -> AdventResult<String> {
+    Ok(Game::from(filename, recursive)?.winning_score().to_string())
 }
 
 #[derive(Debug,Copy,Clone,Eq,PartialEq,Ord,PartialOrd)]
 enum Player {One, Two}
 
 impl Player {
-    fn from(s: String) -> Self {
-        match s.as_str() {
-            "Player 1:" => Player::One,
-            "Player 2:" => Player::Two,
-            _ => panic!("Shouldn't happen")
+    fn from(s: &str) -> Result<Self,String> {
+        match s {
+            "Player 1:" => Ok(Player::One),
+            "Player 2:" => Ok(Player::Two),
+            _ => Err("expected `Player 1:` or `Player 2:`".to_string())
         }
     }
 }
 
-struct Game {
+#[derive(Clone)]
+pub struct Game {
     deck1: Deck, deck2: Deck, recursive: bool, previous_rounds: BTreeSet<(Deck,Deck)>
 }
 
 impl Game {
-    fn from(filename: &str, recursive: bool) -> io::Result<Self> {
-        let mut iter = all_lines(filename)?;
-        let deck1 = Deck::from(&mut iter);
-        let deck2 = Deck::from(&mut iter);
+    pub fn from(filename: &str, recursive: bool) -> AdventResult<Self> {
+        let mut iter = numbered_lines(filename)?;
+        let deck1 = Deck::from(&mut iter)?;
+        let deck2 = Deck::from(&mut iter)?;
         Ok(Game { deck1, deck2, recursive, previous_rounds: BTreeSet::new() })
     }
 
@@ -56,6 +63,10 @@ impl Game {
         }
     }
 
+    pub fn winning_score(&mut self) -> usize {
+        self.play_to_end().1
+    }
+
     fn play_to_end(&mut self) -> (Player,usize) {
         loop {
             if self.repeated() {
@@ -111,13 +122,14 @@ struct Deck {
 }
 
 impl Deck {
-    fn from<I:Iterator<Item=String>>(iter: &mut I) -> Self {
-        let player = Player::from(iter.next().unwrap());
+    fn from<I:Iterator<Item=(usize,String)>>(iter: &mut I) -> AdventResult<Self> {
+        let (line_num, line) = iter.next().ok_or_else(|| AdventError::bad_input(DAY, "missing a player's deck"))?;
+        let player = Player::from(line.as_str()).at_line(DAY, line_num, line.as_str())?;
         let cards = iter
-            .take_while(|s| s.len() > 0)
-            .map(|s| s.parse::<usize>().unwrap())
-            .collect();
-        Deck {player, cards}
+            .take_while(|(_, s)| !s.is_empty())
+            .map(|(line_num, s)| parse_num(s.as_str()).at_line(DAY, line_num, s.as_str()))
+            .collect::<AdventResult<_>>()?;
+        Ok(Deck {player, cards})
     }
 
     fn draw(&mut self) -> usize {
@@ -151,14 +163,24 @@ impl Deck {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(solve_1("in/day22_ex.txt").unwrap(), "306");
+        assert_eq!(solve_1("fixtures/day22_ex.txt").unwrap(), "306");
     }
 
     #[test]
     fn test_puzzle_2() {
-        assert_eq!(solve_2("in/day22_ex.txt").unwrap(), "291");
+        assert_eq!(solve_2("fixtures/day22_ex.txt").unwrap(), "291");
+    }
+
+    #[test]
+    fn test_bad_decks() {
+        let input = InputFile::from_reader("Player 1:\n9\n2\n\nPlayer 3:\n5\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(),
+                   "day 22, line 5: expected `Player 1:` or `Player 2:` in `Player 3:`");
+        let input = InputFile::from_reader("Player 1:\n9\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), "day 22: bad input: missing a player's deck");
     }
 }
\ No newline at end of file
@@ -1,7 +1,45 @@
 use num::Integer;
 use std::collections::BTreeSet;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, LineContext};
+use crate::input::numbered_lines;
 
-pub fn solve_1(cups: [u8; 9]) -> String {
+const DAY: usize = 23;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    Ok(play_1(&starting_cups(filename)?))
+}
+
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    Ok(play_2(&starting_cups(filename)?))
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(23, 1, solve_1);
+    registry.add(23, 2, solve_2);
+}
+
+pub fn starting_cups(filename: &str) -> AdventResult<Vec<u8>> {
+    let (line_num, line) = numbered_lines(filename)?.next().unwrap_or((1, String::new()));
+    parse_cups(line.trim()).at_line(DAY, line_num, line.as_str())
+}
+
+// Labels must be exactly 1..=n, with enough cups to pick up NUM_REMOVE and keep a current cup.
+fn parse_cups(text: &str) -> Result<Vec<u8>,String> {
+    let cups = text.chars()
+        .map(|c| c.to_digit(10).map(|d| d as u8).ok_or(format!("`{}` is not a cup label", c)))
+        .collect::<Result<Vec<_>,String>>()?;
+    if cups.len() < NUM_REMOVE + 1 {
+        return Err(format!("expected at least {} cups, found {}", NUM_REMOVE + 1, cups.len()));
+    }
+    let labels: BTreeSet<u8> = cups.iter().copied().collect();
+    if labels != (1..=cups.len() as u8).collect() {
+        return Err(format!("cup labels must be 1 through {} with no repeats", cups.len()));
+    }
+    Ok(cups)
+}
+
+pub fn play_1(cups: &[u8]) -> String {
     let mut cups = CupRing::new(cups, cups.len());
     for _ in 0..100 {
         cups.move_once()
@@ -9,7 +47,7 @@ pub fn solve_1(cups: [u8; 9]) -> String {
     cups.num_string()
 }
 
-pub fn solve_2(cups: [u8; 9]) -> String {
+pub fn play_2(cups: &[u8]) -> String {
     let mut cups = CupRing::new(cups, 1_000_000);
     for _ in 0..10_000_000 {
         cups.move_once();
@@ -23,7 +61,7 @@ struct CupNode {
     next: usize
 }
 
-struct CupNodeIter<'a> {
+pub struct CupNodeIter<'a> {
     next: usize,
     ring: &'a CupRing
 }
@@ -39,7 +77,7 @@ impl <'a> Iterator for CupNodeIter<'a> {
 }
 
 #[derive(Debug,Clone)]
-struct CupRing {
+pub struct CupRing {
     cups: Vec<CupNode>,
     values2pointers: Vec<usize>,
     current: usize,
@@ -50,7 +88,7 @@ struct CupRing {
 const NUM_REMOVE: usize = 3;
 
 impl CupRing {
-    fn new(ordering: [u8; 9], total: usize) -> Self {
+    pub fn new(ordering: &[u8], total: usize) -> Self {
         let cups: Vec<CupNode> = ordering.iter().map(|v| *v as u32)
             .chain((ordering.len()..total).map(|i| (i+1) as u32))
             .enumerate()
@@ -63,7 +101,7 @@ impl CupRing {
         CupRing { cups, values2pointers, current: 0, min: 1, max: total as u32 }
     }
 
-    fn move_once(&mut self) {
+    pub fn move_once(&mut self) {
         let (dest_ptr, remove_end_ptr) = self.destination_remove_end_ptrs();
         let remove_start_ptr = self.cups[self.current].next;
         let after_dest_ptr = self.cups[dest_ptr].next;
@@ -105,27 +143,27 @@ impl CupRing {
     }
      */
 
-    fn iter_after_1(&self) -> CupNodeIter {
+    pub fn iter_after_1(&self) -> CupNodeIter {
         let mut iter = self.iter();
         iter.by_ref().skip_while(|p| self.cups[*p].value != 1).next();
         iter
     }
 
-    fn num_string(&self) -> String {
+    pub fn num_string(&self) -> String {
         self.iter_after_1()
             .take_while(|p| self.cups[*p].value != 1)
             .map(|p| (self.cups[p].value as u8 + '0' as u8) as char)
             .collect()
     }
 
-    fn star_product(&self) -> u64 {
+    pub fn star_product(&self) -> u64 {
         self.iter_after_1()
             .take(2)
             .map(|p| self.cups[p].value as u64)
             .product()
     }
 
-    fn iter(&self) -> CupNodeIter {
+    pub fn iter(&self) -> CupNodeIter {
         CupNodeIter {next: self.current, ring: &self}
     }
 }
@@ -134,9 +172,11 @@ impl CupRing {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::AdventError;
+    use crate::input::InputFile;
 
     fn puzzle_1_example() -> CupRing {
-        CupRing::new([3, 8, 9, 1, 2, 5, 4, 6, 7], 9)
+        CupRing::new(&[3, 8, 9, 1, 2, 5, 4, 6, 7], 9)
     }
 
     #[test]
@@ -162,7 +202,33 @@ mod tests {
 
     #[test]
     fn bigger_test() {
-        assert_eq!(solve_1([3, 8, 9, 1, 2, 5, 4, 6, 7]), "67384529");
+        assert_eq!(play_1(&[3, 8, 9, 1, 2, 5, 4, 6, 7]), "67384529");
+    }
+
+    #[test]
+    fn test_starting_cups() {
+        let input = InputFile::from_reader("389125467\n".as_bytes()).unwrap();
+        assert_eq!(starting_cups(input.filename()).unwrap(), vec![3, 8, 9, 1, 2, 5, 4, 6, 7]);
+        assert_eq!(solve_1(input.filename()).unwrap(), "67384529");
+        let input = InputFile::from_reader("38x\n".as_bytes()).unwrap();
+        assert_eq!(starting_cups(input.filename()).unwrap_err().to_string(),
+                   "day 23, line 1: `x` is not a cup label in `38x`");
+    }
+
+    #[test]
+    fn test_bad_cups() {
+        for (text, error) in [
+            ("", "day 23, line 1: expected at least 4 cups, found 0 in ``"),
+            ("389", "day 23, line 1: expected at least 4 cups, found 3 in `389`"),
+            ("3801", "day 23, line 1: cup labels must be 1 through 4 with no repeats in `3801`"),
+            ("38912546", "day 23, line 1: cup labels must be 1 through 8 with no repeats in `38912546`"),
+            ("3312", "day 23, line 1: cup labels must be 1 through 4 with no repeats in `3312`")
+        ].iter() {
+            let input = InputFile::from_reader(text.as_bytes()).unwrap();
+            let err = starting_cups(input.filename()).unwrap_err();
+            assert!(matches!(err, AdventError::Parse {..}));
+            assert_eq!(err.to_string(), *error);
+        }
     }
 
     fn assert_no_nodes_lost(ring: &CupRing) {
@@ -172,7 +238,7 @@ mod tests {
 
     #[test]
     fn slightly_big_test() {
-        let mut cups = CupRing::new([3, 8, 9, 1, 2, 5, 4, 6, 7], 1_000_000);
+        let mut cups = CupRing::new(&[3, 8, 9, 1, 2, 5, 4, 6, 7], 1_000_000);
         for _ in 0..20 {
             cups.move_once();
             assert_no_nodes_lost(&cups);
@@ -181,6 +247,6 @@ mod tests {
 
     #[test]
     fn huge_test() {
-        assert_eq!(solve_2([3, 8, 9, 1, 2, 5, 4, 6, 7]), "149245887792");
+        assert_eq!(play_2(&[3, 8, 9, 1, 2, 5, 4, 6, 7]), "149245887792");
     }
 }
\ No newline at end of file
@@ -1,16 +1,36 @@
-use advent_code_lib::{Position, all_lines};
+use advent_code_lib::Position;
 use std::collections::BTreeMap;
-use std::{io, mem};
+use std::mem;
 use enum_iterator::IntoEnumIterator;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, LineContext};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
-    Ok(Floor::from(filename)?.count_color(TileColor::Black).to_string())
+const DAY: usize = 24;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    Ok(black_tiles(&tile_paths(filename)?, 0).to_string())
+}
+
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    Ok(black_tiles(&tile_paths(filename)?, 100).to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
-    let floor = Floor::from(filename)?;
-    let after100 = FloorOfLifeIter {next: Some(floor)}.skip(100).next().unwrap();
-    Ok(after100.count_color(TileColor::Black).to_string())
+// The directions from the reference tile to each tile that gets flipped, one path per line.
+pub fn tile_paths(filename: &str) -> AdventResult<Vec<Vec<HexDir>>> {
+    numbered_lines(filename)?
+        .map(|(line_num, line)| dir_seq(line.as_str()).at_line(DAY, line_num, line.as_str()))
+        .collect()
+}
+
+pub fn black_tiles(paths: &[Vec<HexDir>], days: usize) -> usize {
+    let floor = Floor::from(paths);
+    FloorOfLifeIter {next: Some(floor)}.nth(days).unwrap().count_color(TileColor::Black)
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(24, 1, solve_1);
+    registry.add(24, 2, solve_2);
 }
 
 #[derive(Debug,Eq,PartialEq,Clone)]
@@ -19,17 +39,16 @@ struct Floor {
 }
 
 impl Floor {
-    fn from(filename: &str) -> io::Result<Self> {
+    fn from(paths: &[Vec<HexDir>]) -> Self {
         let mut result = Floor { floor: BTreeMap::new() };
-        all_lines(filename)?.for_each(|line| {
-            let dirs = dir_seq(line.as_str());
+        for dirs in paths.iter() {
             let destination = dirs.iter().fold(Position::new(), |p, hd| hd.next(p));
             match result.floor.get_mut(&destination) {
                 None => {result.floor.insert(destination, TileColor::Black);}
                 Some(color) => {color.flip();}
             }
-        });
-        Ok(result)
+        }
+        result
     }
 
     fn count_color(&self, color: TileColor) -> usize {
@@ -105,30 +124,30 @@ impl TileColor {
 }
 
 #[derive(Copy,Clone,Debug,Eq,PartialEq,IntoEnumIterator)]
-enum HexDir {
+pub enum HexDir {
     E, Se, Sw, W, Nw, Ne
 }
 
 impl HexDir {
-    fn from(first: Option<char>, second: char) -> HexDir {
+    fn from(first: Option<char>, second: char) -> Result<HexDir,String> {
         match first {
             Some(c) => match c {
                 'n' => match second {
-                    'e' => HexDir::Ne,
-                    'w' => HexDir::Nw,
-                    _ => panic!("Unrecognized char: '{}'", second)
+                    'e' => Ok(HexDir::Ne),
+                    'w' => Ok(HexDir::Nw),
+                    _ => Err(format!("Unrecognized char: '{}'", second))
                 }
                 's' => match second {
-                    'e' => HexDir::Se,
-                    'w' => HexDir::Sw,
-                    _ => panic!("Unrecognized char: '{}'", second)
+                    'e' => Ok(HexDir::Se),
+                    'w' => Ok(HexDir::Sw),
+                    _ => Err(format!("Unrecognized char: '{}'", second))
                 }
-                _ => panic!("Unrecognized char: '{}", c)
+                _ => Err(format!("Unrecognized char: '{}'", c))
             }
             None => match second {
-                'e' => HexDir::E,
-                'w' => HexDir::W,
-                _ => panic!("Unrecognized char: '{}'", second)
+                'e' => Ok(HexDir::E),
+                'w' => Ok(HexDir::W),
+                _ => Err(format!("Unrecognized char: '{}'", second))
             }
         }
     }
@@ -150,20 +169,23 @@ impl HexDir {
     }
 }
 
-fn dir_seq(line: &str) -> Vec<HexDir> {
+pub fn dir_seq(line: &str) -> Result<Vec<HexDir>,String> {
     let mut result = Vec::new();
     let mut tentative = None;
-    line.chars().for_each(|c| {
+    for c in line.chars() {
         match c {
-            's' | 'n' => tentative = Some(c),
+            's' | 'n' if tentative.is_none() => tentative = Some(c),
             'e' | 'w' => {
-                result.push(HexDir::from(tentative, c));
+                result.push(HexDir::from(tentative, c)?);
                 tentative = None;
             },
-            _ => panic!("Illegal character: '{}'", c)
+            _ => return Err(format!("Illegal character: '{}'", c))
         }
-    });
-    result
+    }
+    match tentative {
+        Some(c) => Err(format!("Incomplete direction: '{}'", c)),
+        None => Ok(result)
+    }
 }
 
 #[cfg(test)]
@@ -178,18 +200,21 @@ mod tests {
             ("esew", vec![E, Se, W]),
             ("nwwswee", vec![Nw, W, Sw, E, E])
         ].iter().for_each(|(s, v)| {
-            assert_eq!(&dir_seq(s), v);
+            assert_eq!(&dir_seq(s).unwrap(), v);
         });
+        assert_eq!(dir_seq("esex").unwrap_err(), "Illegal character: 'x'");
+        assert_eq!(dir_seq("nse").unwrap_err(), "Illegal character: 's'");
+        assert_eq!(dir_seq("esen").unwrap_err(), "Incomplete direction: 'n'");
     }
 
     #[test]
     fn test_solve_1() {
-        assert_eq!(solve_1("in/day24_ex.txt").unwrap(), "10");
+        assert_eq!(solve_1("fixtures/day24_ex.txt").unwrap(), "10");
     }
 
     #[test]
     fn test_black_adj() {
-        let floor = Floor::from("in/day24_ex.txt").unwrap();
+        let floor = Floor::from(&tile_paths("fixtures/day24_ex.txt").unwrap());
         for (p,c) in floor.floor.iter() {
             println!("{:?}: {:?} ({:?})", p, c, HexDir::neighbors(*p).iter()
                 .filter(|n| floor.floor.contains_key(*n))
@@ -212,7 +237,7 @@ mod tests {
     #[test]
     fn test_floor_of_life() {
         let counts: Vec<_> =
-            FloorOfLifeIter { next: Some(Floor::from("in/day24_ex.txt").unwrap())}
+            FloorOfLifeIter { next: Some(Floor::from(&tile_paths("fixtures/day24_ex.txt").unwrap()))}
                 .map(|floor| floor.count_color(TileColor::Black))
                 .take(101)
                 .collect();
@@ -226,6 +251,6 @@ mod tests {
 
     #[test]
     fn test_solve_2() {
-        assert_eq!(solve_2("in/day24_ex.txt").unwrap(), "2208");
+        assert_eq!(solve_2("fixtures/day24_ex.txt").unwrap(), "2208");
     }
 }
\ No newline at end of file
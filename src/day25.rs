@@ -1,28 +1,62 @@
 use std::mem;
 use num::Integer;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
+
+const DAY: usize = 25;
 
 const PUBLIC_SUBJECT: i64 = 7;
 const LOOP_MOD: i64 = 20_201_227;
 
-pub fn solve_1() -> String {
-    find_encryption_key(19241437, 17346587).to_string()
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    let (card_public, door_public) = public_keys(filename)?;
+    Ok(find_encryption_key(card_public, door_public)?.to_string())
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(25, 1, solve_1);
 }
 
-fn find_encryption_key(card_public: i64, door_public: i64) -> i64 {
-    let card_loop = find_loop_size(card_public);
-    let door_loop = find_loop_size(door_public);
+pub fn public_keys(filename: &str) -> AdventResult<(i64, i64)> {
+    let keys = numbered_lines(filename)?
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(line_num, line)| parse_key(line.trim()).at_line(DAY, line_num, line.as_str()))
+        .collect::<AdventResult<Vec<i64>>>()?;
+    if keys.len() < 2 {
+        Err(AdventError::bad_input(DAY, "expected card and door public keys"))
+    } else {
+        Ok((keys[0], keys[1]))
+    }
+}
+
+// Every transform of 7 lands in 1..LOOP_MOD, so keys outside it can never be found.
+fn parse_key(text: &str) -> Result<i64,String> {
+    let key = parse_num(text)?;
+    if key > 0 && key < LOOP_MOD {
+        Ok(key)
+    } else {
+        Err(format!("public key {} is outside 1..{}", key, LOOP_MOD))
+    }
+}
+
+pub fn find_encryption_key(card_public: i64, door_public: i64) -> AdventResult<i64> {
+    let card_loop = find_loop_size(card_public)?;
+    let door_loop = find_loop_size(door_public)?;
     let guess1 = guess_encryption_key(card_loop, &door_public);
     let guess2 = guess_encryption_key(door_loop, &card_public);
-    assert_eq!(guess1, guess2);
-    guess1
+    if guess1 == guess2 {
+        Ok(guess1)
+    } else {
+        Err(AdventError::no_solution(DAY, "card and door disagree on the encryption key"))
+    }
 }
 
-fn find_loop_size(public_key: i64) -> usize {
+fn find_loop_size(public_key: i64) -> AdventResult<usize> {
     Transform::from(PUBLIC_SUBJECT)
-        .enumerate()
-        .find(|(_, n)| n == &public_key)
-        .map(|(c,_)| c)
-        .unwrap()
+        .take(LOOP_MOD as usize)
+        .position(|n| n == public_key)
+        .ok_or_else(|| AdventError::no_solution(DAY, format!("no loop size produces public key {}", public_key).as_str()))
 }
 
 fn guess_encryption_key(device_1_loop: usize, device_2_public_key: &i64) -> i64 {
@@ -54,15 +88,35 @@ impl Iterator for Transform {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn test_loop_size() {
-        assert_eq!(find_loop_size(5764801), 8);
-        assert_eq!(find_loop_size(17807724), 11);
+        assert_eq!(find_loop_size(5764801).unwrap(), 8);
+        assert_eq!(find_loop_size(17807724).unwrap(), 11);
+        assert_eq!(find_loop_size(LOOP_MOD).unwrap_err().to_string(),
+                   "day 25: no solution: no loop size produces public key 20201227");
     }
 
     #[test]
     fn test_find_key() {
-        assert_eq!(find_encryption_key(17807724, 5764801), 14897079);
+        assert_eq!(find_encryption_key(17807724, 5764801).unwrap(), 14897079);
+    }
+
+    #[test]
+    fn test_public_keys() {
+        let input = InputFile::from_reader("5764801\n17807724\n".as_bytes()).unwrap();
+        assert_eq!(public_keys(input.filename()).unwrap(), (5764801, 17807724));
+        assert_eq!(solve_1(input.filename()).unwrap(), "14897079");
+        let input = InputFile::from_reader("5764801\n".as_bytes()).unwrap();
+        assert_eq!(public_keys(input.filename()).unwrap_err().to_string(),
+                   "day 25: bad input: expected card and door public keys");
+        for (bad, reason) in [("0", "public key 0 is outside 1..20201227"),
+            ("-5", "public key -5 is outside 1..20201227"),
+            ("20201227", "public key 20201227 is outside 1..20201227")].iter() {
+            let input = InputFile::from_reader(format!("5764801\n{}\n", bad).as_bytes()).unwrap();
+            assert_eq!(solve_1(input.filename()).unwrap_err().to_string(),
+                       format!("day 25, line 2: {} in `{}`", reason, bad));
+        }
     }
 }
\ No newline at end of file
@@ -1,65 +1,276 @@
-use std::io;
-use advent_code_lib::for_each_line;
+use std::ops::RangeInclusive;
+use std::collections::HashSet;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext};
+use crate::input::numbered_lines;
 
-fn is_tree(c: u8) -> bool {
-    c == '#' as u8
-}
+const DAY: usize = 3;
+const OPEN: char = '.';
+const TREE: char = '#';
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     Ok(format!("{}", solve_slope(filename, 3, 1)?))
 }
 
-struct SledState {
-    x: usize, y: usize, tree_count: usize, right: usize, down: usize
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    Ok(format!("{}", slope_product(&SlopeMap::from_file(filename)?)))
 }
 
-impl SledState {
-    pub fn new(right: usize, down: usize) -> Self {
-        SledState {x: 0, y: 0, tree_count: 0, right, down}
-    }
+pub fn slope_product(map: &SlopeMap) -> usize {
+    [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)].iter()
+        .map(|(r, d)| map.trees_on(Slope::new(*r, *d).unwrap()))
+        .product()
+}
 
-    pub fn update(&mut self, line: &str) {
-        let line = line.as_bytes();
-        if self.y % self.down == 0 {
-            if self.y > 0 && is_tree(line[self.x % line.len()]) {
-                self.tree_count += 1;
-            }
-            self.x += self.right;
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(3, 1, solve_1);
+    registry.add(3, 2, solve_2);
+}
+
+fn solve_slope(filename: &str, right: isize, down: usize) -> AdventResult<usize> {
+    let slope = Slope::new(right, down).map_err(|e| AdventError::no_solution(DAY, e.as_str()))?;
+    Ok(SlopeMap::from_file(filename)?.trees_on(slope))
+}
+
+// Each step moves `right` columns (negative for leftward) and `down` rows,
+// so any rational slope is expressible by its numerator and denominator.
+#[derive(Debug,Copy,Clone,Eq,PartialEq,Ord,PartialOrd)]
+pub struct Slope {
+    right: isize,
+    down: usize
+}
+
+impl Slope {
+    pub fn new(right: isize, down: usize) -> Result<Self,String> {
+        if down == 0 {
+            Err("a slope must move down at least one row".to_string())
+        } else {
+            Ok(Slope {right, down})
         }
-        self.y += 1;
     }
+
+    pub fn right(&self) -> isize {self.right}
+    pub fn down(&self) -> usize {self.down}
+}
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Objective {
+    FewestTrees, MostTrees
+}
+
+// The grid repeats endlessly to the left and right.
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct SlopeMap {
+    rows: Vec<Vec<bool>>
 }
 
-fn solve_slope(filename: &str, right: usize, down: usize) -> io::Result<usize> {
-    let mut sled = SledState::new(right, down);
-    for_each_line(filename, |line| Ok({
-        sled.update(line);
-    }))?;
-    Ok(sled.tree_count)
+impl SlopeMap {
+    pub fn from_file(filename: &str) -> AdventResult<Self> {
+        let mut rows: Vec<Vec<bool>> = Vec::new();
+        for (line_num, line) in numbered_lines(filename)? {
+            let row = parse_row(line.as_str(), rows.first().map(|r| r.len())).at_line(DAY, line_num, line.as_str())?;
+            rows.push(row);
+        }
+        if rows.is_empty() {
+            return Err(AdventError::bad_input(DAY, "no map rows"));
+        }
+        Ok(SlopeMap {rows})
+    }
+
+    pub fn height(&self) -> usize {self.rows.len()}
+
+    pub fn width(&self) -> usize {self.rows.first().map_or(0, |r| r.len())}
+
+    pub fn is_tree(&self, row: usize, col: isize) -> bool {
+        self.rows[row][self.wrap(col)]
+    }
+
+    fn wrap(&self, col: isize) -> usize {
+        col.rem_euclid(self.width() as isize) as usize
+    }
+
+    // Visited cells as (row, unwrapped column), starting from the top-left corner.
+    pub fn path(&self, slope: Slope) -> Vec<(usize,isize)> {
+        if self.width() == 0 {
+            return Vec::new();
+        }
+        (0..self.height()).step_by(slope.down)
+            .enumerate()
+            .map(|(step, row)| (row, step as isize * slope.right))
+            .collect()
+    }
+
+    // The starting cell is never counted.
+    pub fn trees_on(&self, slope: Slope) -> usize {
+        self.path(slope).iter()
+            .skip(1)
+            .filter(|(row, col)| self.is_tree(*row, *col))
+            .count()
+    }
+
+    // Every slope within the bounds, ordered from fewest to most trees;
+    // ties are broken by slope.
+    pub fn rank_slopes(&self, rights: RangeInclusive<isize>, downs: RangeInclusive<usize>) -> Vec<(Slope,usize)> {
+        let mut ranking: Vec<(Slope,usize)> = rights
+            .flat_map(|right| downs.clone().filter_map(move |down| Slope::new(right, down).ok()))
+            .map(|slope| (slope, self.trees_on(slope)))
+            .collect();
+        ranking.sort_by_key(|(slope, trees)| (*trees, *slope));
+        ranking
+    }
+
+    pub fn best_slope(&self, rights: RangeInclusive<isize>, downs: RangeInclusive<usize>, objective: Objective) -> Option<(Slope,usize)> {
+        let ranking = self.rank_slopes(rights, downs);
+        match objective {
+            Objective::FewestTrees => ranking.first().copied(),
+            Objective::MostTrees => ranking.last()
+                .and_then(|(_, most)| ranking.iter().find(|(_, trees)| trees == most))
+                .copied()
+        }
+    }
+
+    // Repeats the grid horizontally as often as the path needs, marking
+    // visited open squares with `O` and visited trees with `X`.
+    pub fn render(&self, slope: Slope) -> String {
+        let path: HashSet<(usize,isize)> = self.path(slope).into_iter().collect();
+        let width = self.width() as isize;
+        let first_col = path.iter().map(|(_, col)| col.div_euclid(width)).min().unwrap_or(0) * width;
+        let last_col = (path.iter().map(|(_, col)| col.div_euclid(width)).max().unwrap_or(0) + 1) * width;
+        let mut rendered = String::new();
+        for (row, cells) in self.rows.iter().enumerate() {
+            for col in first_col..last_col {
+                let tree = cells[self.wrap(col)];
+                rendered.push(match (path.contains(&(row, col)), tree) {
+                    (true, true) => 'X',
+                    (true, false) => 'O',
+                    (false, true) => TREE,
+                    (false, false) => OPEN
+                });
+            }
+            rendered.push('\n');
+        }
+        rendered
+    }
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
-    Ok(format!("{}", [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)].iter()
-        .map(|(r, d)| solve_slope(filename, *r, *d).unwrap())
-        .product::<usize>()))
+fn parse_row(line: &str, width: Option<usize>) -> Result<Vec<bool>,String> {
+    let row = line.chars()
+        .map(|c| match c {
+            TREE => Ok(true),
+            OPEN => Ok(false),
+            _ => Err(format!("Unrecognized square '{}'", c))
+        })
+        .collect::<Result<Vec<bool>,String>>()?;
+    if row.is_empty() {
+        return Err("empty map row".to_string());
+    }
+    match width {
+        Some(width) if width != row.len() => Err(format!("expected {} squares, found {}", width, row.len())),
+        _ => Ok(row)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     fn example_1() {
-        assert_eq!(solve_1("in/day3_ex.txt").unwrap(), "7");
+        assert_eq!(solve_1("fixtures/day3_ex.txt").unwrap(), "7");
     }
 
     #[test]
     fn example_2() {
-        assert_eq!(solve_2("in/day3_ex.txt").unwrap(), "336")
+        assert_eq!(solve_2("fixtures/day3_ex.txt").unwrap(), "336")
     }
 
     #[test]
     fn example_3() {
-        assert_eq!(solve_slope("in/day3_ex.txt", 1, 2).unwrap(), 2)
+        assert_eq!(solve_slope("fixtures/day3_ex.txt", 1, 2).unwrap(), 2)
+    }
+
+    #[test]
+    fn test_path() {
+        let map = SlopeMap::from_file("fixtures/day3_ex.txt").unwrap();
+        assert_eq!((map.height(), map.width()), (11, 11));
+        assert_eq!(map.path(Slope::new(3, 1).unwrap())[..4], [(0, 0), (1, 3), (2, 6), (3, 9)]);
+        assert_eq!(map.path(Slope::new(1, 2).unwrap()), vec![(0, 0), (2, 1), (4, 2), (6, 3), (8, 4), (10, 5)]);
+        assert_eq!(map.path(Slope::new(-2, 5).unwrap()), vec![(0, 0), (5, -2), (10, -4)]);
+        assert!(map.is_tree(4, -2));
+        assert_eq!(map.trees_on(Slope::new(-8, 1).unwrap()), 7);
+        assert_eq!(Slope::new(1, 0).unwrap_err(), "a slope must move down at least one row");
+    }
+
+    #[test]
+    fn test_best_slope() {
+        let map = SlopeMap::from_file("fixtures/day3_ex.txt").unwrap();
+        assert_eq!(map.best_slope(1..=7, 1..=2, Objective::FewestTrees), Some((Slope::new(5, 2).unwrap(), 0)));
+        assert_eq!(map.best_slope(1..=7, 1..=2, Objective::MostTrees), Some((Slope::new(3, 1).unwrap(), 7)));
+        assert_eq!(map.best_slope(-3..=-1, 1..=1, Objective::FewestTrees), Some((Slope::new(-2, 1).unwrap(), 2)));
+        assert_eq!(map.best_slope(1..=7, 0..=0, Objective::MostTrees), None);
+    }
+
+    #[test]
+    fn test_rank_slopes() {
+        let map = SlopeMap::from_file("fixtures/day3_ex.txt").unwrap();
+        let ranking = map.rank_slopes(-3..=7, 0..=2);
+        assert_eq!(ranking.len(), 22);
+        assert_eq!(ranking[..4], [(Slope::new(5, 2).unwrap(), 0), (Slope::new(-3, 2).unwrap(), 1),
+            (Slope::new(0, 2).unwrap(), 1), (Slope::new(2, 1).unwrap(), 1)]);
+        assert_eq!(ranking[ranking.len() - 2..], [(Slope::new(-1, 1).unwrap(), 5), (Slope::new(3, 1).unwrap(), 7)]);
+        for (slope, trees) in [(1, 1), (3, 1), (5, 1), (7, 1), (1, 2)].iter().zip([2, 7, 3, 4, 2].iter()) {
+            let slope = Slope::new(slope.0, slope.1).unwrap();
+            assert!(ranking.contains(&(slope, *trees)));
+        }
+    }
+
+    #[test]
+    fn test_render() {
+        let map = SlopeMap::from_file("fixtures/day3_ex.txt").unwrap();
+        assert_eq!(map.render(Slope::new(3, 1).unwrap()), "\
+O.##.........##.........##.......
+#..O#...#..#...#...#..#...#...#..
+.#....X..#..#....#..#..#....#..#.
+..#.#...#O#..#.#...#.#..#.#...#.#
+.#...##..#..X...##..#..#...##..#.
+..#.##.......#.X#.......#.##.....
+.#.#.#....#.#.#.#.O..#.#.#.#....#
+.#........#.#........X.#........#
+#.##...#...#.##...#...#.X#...#...
+#...##....##...##....##...#X....#
+.#..#...#.#.#..#...#.#.#..#...X.#
+");
+        assert_eq!(map.render(Slope::new(-1, 5).unwrap()), "\
+..##.......O.##.......
+#...#...#..#...#...#..
+.#....#..#..#....#..#.
+..#.#...#.#..#.#...#.#
+.#...##..#..#...##..#.
+..#.##....O..#.##.....
+.#.#.#....#.#.#.#....#
+.#........#.#........#
+#.##...#...#.##...#...
+#...##....##...##....#
+.#..#...#O#.#..#...#.#
+");
+    }
+
+    #[test]
+    fn test_bad_map() {
+        let input = InputFile::from_reader("..#\n.#\n".as_bytes()).unwrap();
+        assert_eq!(SlopeMap::from_file(input.filename()).unwrap_err().to_string(),
+                   "day 3, line 2: expected 3 squares, found 2 in `.#`");
+        let input = InputFile::from_reader("..#\n.@.\n".as_bytes()).unwrap();
+        assert_eq!(SlopeMap::from_file(input.filename()).unwrap_err().to_string(),
+                   "day 3, line 2: Unrecognized square '@' in `.@.`");
+        let input = InputFile::from_reader("..#\n\n".as_bytes()).unwrap();
+        assert_eq!(SlopeMap::from_file(input.filename()).unwrap_err().to_string(),
+                   "day 3, line 2: empty map row in ``");
+        let input = InputFile::from_reader("\n..#\n".as_bytes()).unwrap();
+        assert_eq!(SlopeMap::from_file(input.filename()).unwrap_err().to_string(),
+                   "day 3, line 1: empty map row in ``");
+        let input = InputFile::from_reader("".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(), "day 3: bad input: no map rows");
     }
 }
\ No newline at end of file
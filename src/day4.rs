@@ -1,91 +1,421 @@
-use std::io;
-use advent_code_lib::MultiLineObjects;
-use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::collections::{BTreeMap, BTreeSet};
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
-    solve(filename, has_all)
+const DAY: usize = 4;
+const DEFAULT_SCHEMA: &str = include_str!("day4_schema.txt");
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    solve(filename, |schema, passport| schema.has_required(passport))
+}
+
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    solve(filename, |schema, passport| schema.accepts(passport))
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(4, 1, solve_1);
+    registry.add(4, 2, solve_2);
+}
+
+fn solve<P: Fn(&Schema, &BTreeMap<String,String>) -> bool>(filename: &str, predicate: P) -> AdventResult<String> {
+    let schema = Schema::default();
+    Ok(count_matching(&parse_passports(filename, &schema)?, &schema, predicate).to_string())
+}
+
+pub fn count_matching<P: Fn(&Schema, &BTreeMap<String,String>) -> bool>(passports: &[Passport], schema: &Schema, predicate: P) -> usize {
+    passports.iter().filter(|passport| predicate(schema, &passport.fields)).count()
+}
+
+pub fn count_valid(filename: &str, schema: &Schema, mode: Mode) -> AdventResult<(usize,usize)> {
+    let reports = validation_reports(filename, schema, mode)?;
+    Ok((reports.iter().filter(|r| r.is_valid()).count(), reports.len()))
+}
+
+// Strict mode rejects any passport that produced a parse warning. Lenient
+// mode only rejects unknown fields, as the puzzle does.
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Mode {
+    Lenient, Strict
+}
+
+// Fields absent from a schema are never valid.
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Schema {
+    fields: BTreeMap<String,FieldSpec>
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct FieldSpec {
+    pub required: bool,
+    pub rule: Rule
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum Rule {
+    Any,
+    Int {range: RangeInclusive<usize>, digits: Option<usize>},
+    Units(Vec<(String,RangeInclusive<usize>)>),
+    Pattern(Pattern),
+    OneOf(BTreeSet<String>)
+}
+
+impl Default for Schema {
+    fn default() -> Self {
+        Schema::from_lines(DEFAULT_SCHEMA.lines().enumerate().map(|(i, line)| (i + 1, line.to_string()))).unwrap()
+    }
+}
+
+impl Schema {
+    pub fn from_file(filename: &str) -> AdventResult<Self> {
+        Schema::from_lines(numbered_lines(filename)?)
+    }
+
+    // One field per line: `name required|optional rule...`; blank lines and
+    // lines starting with `#` are ignored.
+    pub fn from_lines<I: Iterator<Item=(usize,String)>>(lines: I) -> AdventResult<Self> {
+        let mut fields = BTreeMap::new();
+        for (line_num, line) in lines {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let (name, spec) = parse_field_spec(line.as_str()).at_line(DAY, line_num, line.as_str())?;
+            fields.insert(name, spec);
+        }
+        Ok(Schema {fields})
+    }
+
+    pub fn field(&self, name: &str) -> Option<&FieldSpec> {
+        self.fields.get(name)
+    }
+
+    pub fn has_required(&self, passport: &BTreeMap<String,String>) -> bool {
+        self.fields.iter()
+            .filter(|(_, spec)| spec.required)
+            .all(|(name, _)| passport.contains_key(name))
+    }
+
+    pub fn accepts(&self, passport: &BTreeMap<String,String>) -> bool {
+        self.has_required(passport) && passport.iter().all(|(k, v)| self.valid_field(k.as_str(), v.as_str()))
+    }
+
+    pub fn valid_field(&self, field: &str, value: &str) -> bool {
+        self.check_field(field, value).is_ok()
+    }
+
+    pub fn check_field(&self, field: &str, value: &str) -> Result<(),String> {
+        match self.fields.get(field) {
+            Some(spec) => spec.rule.check(value),
+            None => Err("not in schema".to_string())
+        }
+    }
+
+    pub fn is_known(&self, field: &str) -> bool {
+        self.fields.contains_key(field)
+    }
+
+    // Unknown fields appear among the passport's warnings rather than as invalid fields.
+    pub fn validate(&self, index: usize, passport: &Passport, mode: Mode) -> PassportReport {
+        PassportReport {
+            index,
+            mode,
+            missing: self.fields.iter()
+                .filter(|(name, spec)| spec.required && !passport.fields.contains_key(*name))
+                .map(|(name, _)| name.clone())
+                .collect(),
+            invalid: passport.fields.iter()
+                .filter(|(field, _)| self.is_known(field))
+                .filter_map(|(field, value)| self.check_field(field, value).err()
+                    .map(|reason| FieldError {field: field.clone(), value: value.clone(), reason}))
+                .collect(),
+            warnings: passport.warnings.clone()
+        }
+    }
+}
+
+// Passports are numbered from 1 in file order.
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct PassportReport {
+    pub index: usize,
+    pub mode: Mode,
+    pub missing: Vec<String>,
+    pub invalid: Vec<FieldError>,
+    pub warnings: Vec<ParseWarning>
+}
+
+impl PassportReport {
+    pub fn is_valid(&self) -> bool {
+        self.missing.is_empty() && self.invalid.is_empty() && match self.mode {
+            Mode::Strict => self.warnings.is_empty(),
+            Mode::Lenient => !self.warnings.iter().any(|w| matches!(w, ParseWarning::Unknown {..}))
+        }
+    }
+}
+
+impl fmt::Display for PassportReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut problems: Vec<String> = Vec::new();
+        if !self.missing.is_empty() {
+            problems.push(format!("missing {}", self.missing.join(", ")));
+        }
+        problems.extend(self.invalid.iter().map(|e| e.to_string()));
+        problems.extend(self.warnings.iter().map(|w| w.to_string()));
+        write!(f, "passport {}: {}", self.index, if problems.is_empty() {"valid".to_string()} else {problems.join("; ")})
+    }
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub value: String,
+    pub reason: String
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.field, self.value, self.reason)
+    }
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
-    solve(filename, all_valid)
+pub fn validation_reports(filename: &str, schema: &Schema, mode: Mode) -> AdventResult<Vec<PassportReport>> {
+    Ok(parse_passports(filename, schema)?.iter()
+        .enumerate()
+        .map(|(i, passport)| schema.validate(i + 1, passport, mode))
+        .collect())
 }
 
-fn solve<P: Fn(&BTreeMap<String,String>) -> bool>(filename: &str, predicate: P) -> io::Result<String> {
-    let count = fields_and_values_from(filename)?.count_matching(predicate);
-    Ok(count.to_string())
+pub fn validation_report(filename: &str, schema: &Schema, mode: Mode) -> AdventResult<String> {
+    let reports = validation_reports(filename, schema, mode)?;
+    let mut report = format!("{} of {} passports valid\n", reports.iter().filter(|r| r.is_valid()).count(), reports.len());
+    for failed in reports.iter().filter(|r| !r.is_valid()) {
+        report.push_str(format!("{}\n", failed).as_str());
+    }
+    Ok(report)
+}
+
+fn parse_field_spec(line: &str) -> Result<(String,FieldSpec),String> {
+    let mut words = line.split_whitespace();
+    let name = words.next().ok_or("missing field name")?.to_string();
+    let required = match words.next() {
+        Some("required") => true,
+        Some("optional") => false,
+        Some(other) => return Err(format!("expected `required` or `optional`, found `{}`", other)),
+        None => return Err(format!("`{}` needs required/optional and a rule", name))
+    };
+    let rule = Rule::parse(&words.collect::<Vec<_>>())?;
+    Ok((name, FieldSpec {required, rule}))
+}
+
+fn parse_range(text: &str) -> Result<RangeInclusive<usize>,String> {
+    let mut bounds = text.split("..=");
+    match (bounds.next(), bounds.next(), bounds.next()) {
+        (Some(lo), Some(hi), None) => Ok(parse_num(lo)?..=parse_num(hi)?),
+        _ => Err(format!("`{}` is not a range like 1..=10", text))
+    }
+}
+
+impl Rule {
+    pub fn parse(words: &[&str]) -> Result<Self,String> {
+        match words {
+            ["any"] => Ok(Rule::Any),
+            ["int", range] => Ok(Rule::Int {range: parse_range(range)?, digits: None}),
+            ["int", range, "digits", n] => Ok(Rule::Int {range: parse_range(range)?, digits: Some(parse_num(n)?)}),
+            ["units", units @ ..] if !units.is_empty() && units.len() % 2 == 0 => units.chunks(2)
+                .map(|pair| Ok((pair[0].to_string(), parse_range(pair[1])?)))
+                .collect::<Result<Vec<_>,String>>()
+                .map(Rule::Units),
+            ["pattern", pattern] => Ok(Rule::Pattern(Pattern::parse(pattern)?)),
+            ["one-of", values @ ..] if !values.is_empty() => Ok(Rule::OneOf(values.iter().map(|v| v.to_string()).collect())),
+            _ => Err(format!("Unrecognized rule `{}`", words.join(" ")))
+        }
+    }
+
+    pub fn accepts(&self, value: &str) -> bool {
+        self.check(value).is_ok()
+    }
+
+    pub fn check(&self, value: &str) -> Result<(),String> {
+        match self {
+            Rule::Any => Ok(()),
+            Rule::Int {range, digits} => match digits {
+                Some(n) if value.len() != *n => Err(format!("expected {} digits", n)),
+                _ => check_range(value, range)
+            },
+            Rule::Units(units) => match units.iter().find(|(unit, _)| value.ends_with(unit.as_str())) {
+                Some((unit, range)) => check_range(&value[..value.len() - unit.len()], range),
+                None => Err(format!("missing unit ({})", units.iter().map(|(unit, _)| unit.as_str()).collect::<Vec<_>>().join(", ")))
+            },
+            Rule::Pattern(pattern) if pattern.matches(value) => Ok(()),
+            Rule::Pattern(pattern) => Err(format!("does not match {}", pattern.text)),
+            Rule::OneOf(values) if values.contains(value) => Ok(()),
+            Rule::OneOf(values) => Err(format!("not one of {}", values.iter().map(|v| v.as_str()).collect::<Vec<_>>().join(", ")))
+        }
+    }
 }
 
-fn has_all(passport: &BTreeMap<String,String>) -> bool {
-    ["byr", "iyr", "eyr", "hgt", "hcl", "ecl", "pid"].iter()
-        .all(|field| passport.contains_key(*field))
+fn check_range(value: &str, range: &RangeInclusive<usize>) -> Result<(),String> {
+    let n: usize = parse_num(value)?;
+    if range.contains(&n) {
+        Ok(())
+    } else {
+        Err(format!("out of range {}..={}", range.start(), range.end()))
+    }
+}
+
+// A small subset of regular expressions: literal characters (`\\` escapes),
+// classes such as `[0-9a-f]`, each optionally followed by `*`, `+` or `{n}`.
+// Patterns always match the whole value.
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Pattern {
+    text: String,
+    elements: Vec<(Vec<RangeInclusive<char>>,Repeat)>
 }
 
-fn all_valid(passport: &BTreeMap<String,String>) -> bool {
-    has_all(passport) && passport.iter().all(|(k, v)| valid_field(k.as_str(), v.as_str()))
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+enum Repeat {
+    Exactly(usize), AtLeast(usize)
 }
 
-fn valid_field(field: &str, value: &str) -> bool {
-    match field {
-        "byr" => validate_year(value, 1920, 2002),
-        "iyr" => validate_year(value, 2010, 2020),
-        "eyr" => validate_year(value, 2020, 2030),
-        "hgt" => validate_height(value),
-        "hcl" => validate_hair(value),
-        "ecl" => btreeset!("amb", "blu", "brn", "gry", "grn", "hzl", "oth").contains(value),
-        "pid" => validate_pid(value),
-        "cid" => true,
-        _ => false
+impl Pattern {
+    pub fn parse(text: &str) -> Result<Self,String> {
+        let mut elements = Vec::new();
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            let class = match c {
+                '[' => parse_class(&mut chars, text)?,
+                '\\' => chars.next().map(|c| vec![c..=c]).ok_or(format!("dangling escape in `{}`", text))?,
+                '*' | '+' | '{' | ']' => return Err(format!("unexpected `{}` in `{}`", c, text)),
+                c => vec![c..=c]
+            };
+            let repeat = match chars.peek() {
+                Some('*') => {chars.next(); Repeat::AtLeast(0)}
+                Some('+') => {chars.next(); Repeat::AtLeast(1)}
+                Some('{') => {
+                    chars.next();
+                    let count: String = take_until(&mut chars, '}')
+                        .ok_or(format!("unterminated repetition in `{}`", text))?
+                        .iter().collect();
+                    Repeat::Exactly(parse_num(count.as_str())?)
+                }
+                _ => Repeat::Exactly(1)
+            };
+            elements.push((class, repeat));
+        }
+        Ok(Pattern {text: text.to_string(), elements})
+    }
+
+    pub fn matches(&self, value: &str) -> bool {
+        let value: Vec<char> = value.chars().collect();
+        matches_from(&self.elements, &value)
     }
 }
 
-fn validate_year(value: &str, min: usize, max: usize) -> bool {
-    value.len() == 4 && in_range(value, min, max)
+// The characters before `close`, or `None` if `close` never appears.
+fn take_until<I: Iterator<Item=char>>(chars: &mut I, close: char) -> Option<Vec<char>> {
+    let mut taken = Vec::new();
+    for c in chars {
+        if c == close {
+            return Some(taken);
+        }
+        taken.push(c);
+    }
+    None
 }
 
-fn validate_height(value: &str) -> bool {
-    if value.ends_with("cm") {
-        in_range(&value[..value.len() - 2], 150, 193)
-    } else if value.ends_with("in") {
-        in_range(&value[..value.len() - 2], 59, 76)
+fn parse_class<I: Iterator<Item=char>>(chars: &mut I, text: &str) -> Result<Vec<RangeInclusive<char>>,String> {
+    let members = take_until(chars, ']').ok_or(format!("unterminated character class in `{}`", text))?;
+    let mut class = Vec::new();
+    let mut i = 0;
+    while i < members.len() {
+        if i + 2 < members.len() && members[i + 1] == '-' {
+            class.push(members[i]..=members[i + 2]);
+            i += 3;
+        } else {
+            class.push(members[i]..=members[i]);
+            i += 1;
+        }
+    }
+    if class.is_empty() {
+        Err(format!("empty character class in `{}`", text))
     } else {
-        false
+        Ok(class)
     }
 }
 
-fn validate_hair(value: &str) -> bool {
-    let mut v_iter = value.chars();
-    match v_iter.next() {
-        None => false,
-        Some(c) => c == '#' && v_iter.all(|c| c.is_digit(10) || c >= 'a' && c <= 'f')
+fn matches_from(elements: &[(Vec<RangeInclusive<char>>,Repeat)], value: &[char]) -> bool {
+    match elements.split_first() {
+        None => value.is_empty(),
+        Some(((class, repeat), rest)) => {
+            let run = value.iter().take_while(|c| class.iter().any(|r| r.contains(c))).count();
+            match repeat {
+                Repeat::Exactly(n) => run >= *n && matches_from(rest, &value[*n..]),
+                Repeat::AtLeast(n) => (*n..=run).rev().any(|taken| matches_from(rest, &value[taken..]))
+            }
+        }
     }
 }
 
-fn validate_pid(value: &str) -> bool {
-    value.len() == 9 && value.chars().all(|d| d.is_digit(10))
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Passport {
+    pub line: usize,
+    pub fields: BTreeMap<String,String>,
+    pub warnings: Vec<ParseWarning>
 }
 
-fn in_range(value: &str, min: usize, max: usize) -> bool {
-    match value.parse::<usize>() {
-        Err(_) => false,
-        Ok(value) => value >= min && value <= max
+// When a field repeats, the last value wins.
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum ParseWarning {
+    Duplicate {line: usize, field: String, previous: String},
+    Unknown {line: usize, field: String},
+    Malformed {line: usize, token: String}
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseWarning::Duplicate {line, field, previous} => write!(f, "line {}: duplicate {} replaces {}", line, field, previous),
+            ParseWarning::Unknown {line, field} => write!(f, "line {}: unknown field {}", line, field),
+            ParseWarning::Malformed {line, token} => write!(f, "line {}: malformed token `{}`", line, token)
+        }
     }
 }
 
-fn fields_and_values_from(filename: &str) -> io::Result<MultiLineObjects<BTreeMap<String,String>>> {
-    let passports = MultiLineObjects::from_file
-        (filename,
-         |map: &mut BTreeMap<String,String>, line: &str| {
-             for pair in line.split_whitespace() {
-                 let parts: Vec<&str> = pair.split(':').collect();
-                 map.insert(parts[0].to_owned(), parts[1].to_owned());
-             }
-         })?;
+// Passports are separated by blank lines; each token is `key:value`.
+pub fn parse_passports(filename: &str, schema: &Schema) -> AdventResult<Vec<Passport>> {
+    let mut passports = Vec::new();
+    let mut current: Option<Passport> = None;
+    for (line_num, line) in numbered_lines(filename)? {
+        if line.trim().is_empty() {
+            passports.extend(current.take());
+            continue;
+        }
+        let passport = current.get_or_insert_with(|| Passport {line: line_num, fields: BTreeMap::new(), warnings: Vec::new()});
+        for token in line.split_whitespace() {
+            let mut parts = token.splitn(2, ':');
+            match (parts.next(), parts.next()) {
+                (Some(field), Some(value)) if !field.is_empty() => {
+                    if !schema.is_known(field) {
+                        passport.warnings.push(ParseWarning::Unknown {line: line_num, field: field.to_string()});
+                    }
+                    if let Some(previous) = passport.fields.insert(field.to_string(), value.to_string()) {
+                        passport.warnings.push(ParseWarning::Duplicate {line: line_num, field: field.to_string(), previous});
+                    }
+                }
+                _ => passport.warnings.push(ParseWarning::Malformed {line: line_num, token: token.to_string()})
+            }
+        }
+    }
+    passports.extend(current);
     Ok(passports)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     fn stringify_map(m: &BTreeMap<&str,&str>) -> BTreeMap<String,String> {
         m.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
@@ -108,35 +438,153 @@ mod tests {
             "iyr"=>"2011", "ecl"=>"brn", "hgt"=>"59in")
         ]);
 
-        assert_eq!(fields_and_values_from("in/day4_ex1.txt").unwrap().objects(), example_target);
+        let passports = parse_passports("fixtures/day4_ex1.txt", &Schema::default()).unwrap();
+        assert_eq!(passports.iter().map(|p| p.fields.clone()).collect::<Vec<_>>(), example_target);
+        assert_eq!(passports.iter().map(|p| p.line).collect::<Vec<_>>(), vec![1, 4, 7, 12]);
+        assert!(passports.iter().all(|p| p.warnings.is_empty()));
     }
 
     #[test]
     fn test_1_example() {
-        assert_eq!(solve_1("in/day4_ex1.txt").unwrap(), "2");
+        assert_eq!(solve_1("fixtures/day4_ex1.txt").unwrap(), "2");
     }
 
     #[test]
     fn test_2_values() {
+        let schema = Schema::default();
         for (field, value) in [("byr", "2002"), ("hgt", "60in"), ("hgt", "190cm"),
             ("hcl", "#123abc"), ("ecl", "brn"), ("pid", "000000001")].iter() {
-            assert!(valid_field(field, value));
+            assert!(schema.valid_field(field, value));
         }
 
         for (field, value) in [("byr", "2003"), ("hgt", "190in"),
             ("hgt", "190"), ("hcl", "#123abz"), ("hcl", "123abc"), ("ecl", "wat"),
             ("pid", "0123456789")].iter() {
-            assert!(!valid_field(field, value));
+            assert!(!schema.valid_field(field, value));
         }
     }
 
     #[test]
     fn test_2_example_1() {
-        assert_eq!(solve_2("in/day4_ex1.txt").unwrap(), "2");
+        assert_eq!(solve_2("fixtures/day4_ex1.txt").unwrap(), "2");
     }
 
     #[test]
     fn test_2_example_2() {
-        assert_eq!(solve_2("in/day4_ex2.txt").unwrap(), "4");
+        assert_eq!(solve_2("fixtures/day4_ex2.txt").unwrap(), "4");
+    }
+
+    #[test]
+    fn test_pattern() {
+        let pattern = Pattern::parse("#[0-9a-f]{2}x*\\+").unwrap();
+        for value in ["#0f+", "#a9xxx+"].iter() {
+            assert!(pattern.matches(value));
+        }
+        for value in ["#0f", "0f+", "#0g+", "#0f0+", "#0fx+x"].iter() {
+            assert!(!pattern.matches(value));
+        }
+        assert!(Pattern::parse("[a-z]+[0-9]").unwrap().matches("abc1"));
+        assert!(!Pattern::parse("[a-z]+[0-9]").unwrap().matches("1"));
+        assert_eq!(Pattern::parse("a{x}").unwrap_err(), "`x` is not a number");
+        assert_eq!(Pattern::parse("[]").unwrap_err(), "empty character class in `[]`");
+        assert_eq!(Pattern::parse("[0-9]{3").unwrap_err(), "unterminated repetition in `[0-9]{3`");
+        assert_eq!(Pattern::parse("#[0-9").unwrap_err(), "unterminated character class in `#[0-9`");
+    }
+
+    #[test]
+    fn test_schema_file() {
+        let input = InputFile::from_reader("\
+# a looser schema
+hgt required units cm 100..=250
+pid required pattern [0-9]+
+ecl optional one-of blu grn
+".as_bytes()).unwrap();
+        let schema = Schema::from_file(input.filename()).unwrap();
+        assert_eq!(schema.field("ecl"), Some(&FieldSpec {required: false, rule: Rule::OneOf(btreeset!("blu".to_string(), "grn".to_string()))}));
+        assert!(schema.accepts(&stringify_map(&btreemap!("hgt"=>"240cm", "pid"=>"12"))));
+        assert!(!schema.accepts(&stringify_map(&btreemap!("hgt"=>"60in", "pid"=>"12"))));
+        assert!(!schema.accepts(&stringify_map(&btreemap!("hgt"=>"240cm", "pid"=>"12", "cid"=>"1"))));
+        assert_eq!(count_valid("fixtures/day4_ex2.txt", &schema, Mode::Lenient).unwrap(), (0, 8));
+        assert_eq!(count_valid("fixtures/day4_ex2.txt", &Schema::default(), Mode::Lenient).unwrap(), (4, 8));
+    }
+
+    #[test]
+    fn test_bad_schema() {
+        for (text, error) in [
+            ("byr needed any", "day 4, line 1: expected `required` or `optional`, found `needed` in `byr needed any`"),
+            ("byr required int 1920-2002", "day 4, line 1: `1920-2002` is not a range like 1..=10 in `byr required int 1920-2002`"),
+            ("hgt required units cm", "day 4, line 1: Unrecognized rule `units cm` in `hgt required units cm`")
+        ].iter() {
+            let input = InputFile::from_reader(text.as_bytes()).unwrap();
+            assert_eq!(Schema::from_file(input.filename()).unwrap_err().to_string(), *error);
+        }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_check_reasons() {
+        let schema = Schema::default();
+        for (field, value, reason) in [
+            ("hgt", "190in", "out of range 59..=76"),
+            ("hgt", "190", "missing unit (cm, in)"),
+            ("hgt", "xcm", "`x` is not a number"),
+            ("byr", "02002", "expected 4 digits"),
+            ("byr", "2003", "out of range 1920..=2002"),
+            ("hcl", "123abc", "does not match #[0-9a-f]*"),
+            ("ecl", "wat", "not one of amb, blu, brn, grn, gry, hzl, oth"),
+            ("xyz", "1", "not in schema")
+        ].iter() {
+            assert_eq!(schema.check_field(field, value).unwrap_err(), *reason);
+        }
+    }
+
+    #[test]
+    fn test_validation_report() {
+        let reports = validation_reports("fixtures/day4_ex1.txt", &Schema::default(), Mode::Lenient).unwrap();
+        assert_eq!(reports.iter().map(|r| r.is_valid()).collect::<Vec<_>>(), vec![true, false, true, false]);
+        assert_eq!(reports[1].missing, vec!["hgt"]);
+        assert_eq!(reports[3].missing, vec!["byr"]);
+        assert_eq!(validation_report("fixtures/day4_ex2.txt", &Schema::default(), Mode::Lenient).unwrap(), "\
+4 of 8 passports valid
+passport 1: eyr 1972 out of range 2020..=2030; hgt 170 missing unit (cm, in); pid 186cm does not match [0-9]{9}
+passport 2: eyr 1967 out of range 2020..=2030
+passport 3: hcl dab227 does not match #[0-9a-f]*
+passport 4: byr 2007 out of range 1920..=2002; ecl zzz not one of amb, blu, brn, grn, gry, hzl, oth; \
+eyr 2038 out of range 2020..=2030; hcl 74454a does not match #[0-9a-f]*; hgt 59cm out of range 150..=193; \
+iyr 2023 out of range 2010..=2020; pid 3556412378 does not match [0-9]{9}
+");
+    }
+
+    #[test]
+    fn test_parse_warnings() {
+        let input = InputFile::from_reader("\
+byr:1937 iyr:2017 eyr:2020 hgt:183cm
+hcl:#fffffd ecl:gry pid:860033327 byr:1938
+
+byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd
+ecl:gry pid:860033327 nickname:bob
+
+byr:1937 iyr:2017 eyr:2020 hgt:183cm hcl:#fffffd ecl:gry pid:860033327 smudge :x
+".as_bytes()).unwrap();
+        let schema = Schema::default();
+        let passports = parse_passports(input.filename(), &schema).unwrap();
+        assert_eq!(passports[0].fields["byr"], "1938");
+        assert_eq!(passports[0].warnings, vec![ParseWarning::Duplicate {line: 2, field: "byr".to_string(), previous: "1937".to_string()}]);
+        assert_eq!(passports[1].warnings, vec![ParseWarning::Unknown {line: 5, field: "nickname".to_string()}]);
+        assert_eq!(passports[2].warnings.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+                   vec!["line 7: malformed token `smudge`", "line 7: malformed token `:x`"]);
+
+        assert_eq!(count_valid(input.filename(), &schema, Mode::Lenient).unwrap(), (2, 3));
+        assert_eq!(count_valid(input.filename(), &schema, Mode::Strict).unwrap(), (0, 3));
+        assert_eq!(solve_2(input.filename()).unwrap(), "2");
+        assert_eq!(validation_report(input.filename(), &schema, Mode::Strict).unwrap(), "\
+0 of 3 passports valid
+passport 1: line 2: duplicate byr replaces 1937
+passport 2: line 5: unknown field nickname
+passport 3: line 7: malformed token `smudge`; line 7: malformed token `:x`
+");
+        assert_eq!(validation_report(input.filename(), &schema, Mode::Lenient).unwrap(), "\
+2 of 3 passports valid
+passport 2: line 5: unknown field nickname
+");
+    }
+}
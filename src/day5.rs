@@ -1,56 +1,112 @@
-use std::io;
-use advent_code_lib::all_lines;
-use std::fmt::Display;
+use std::ops::{Range, RangeInclusive};
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext};
+use crate::input::numbered_lines;
 
-fn seat_ids() -> io::Result<impl Iterator<Item=usize>> {
-    Ok(all_lines("in/day5.txt")?
-        .map(|line| BoardingPass::from(line.as_str()).seat_id()))
+const DAY: usize = 5;
+
+pub fn seat_ids(filename: &str) -> AdventResult<Vec<usize>> {
+    let codec = PassCodec::default();
+    numbered_lines(filename)?
+        .map(|(line_num, line)| codec.decode(line.as_str())
+            .at_line(DAY, line_num, line.as_str())
+            .map(|pass| codec.seat_id(&pass)))
+        .collect()
 }
 
-pub fn solve_1() -> io::Result<String> {
-    Ok(seat_ids()?.max().unwrap().to_string())
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    seat_ids(filename)?.iter().max()
+        .map(|id| id.to_string())
+        .ok_or_else(|| AdventError::no_solution(DAY, "no boarding passes"))
 }
 
-pub fn solve_2() -> io::Result<String> {
-    let mut ids: Vec<usize> = seat_ids()?.collect();
-    ids.sort();
-    for i in 1..ids.len() {
-        if ids[i-1] + 2 == ids[i] {
-            return Ok((ids[i-1] + 1).to_string())
-        }
-    }
-    panic!("This shouldn't happen; the seat isn't there")
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    SeatMap::from_file(filename, PassCodec::default())?.single_gaps().first()
+        .map(|id| id.to_string())
+        .ok_or_else(|| AdventError::no_solution(DAY, "no single-seat gap between boarding passes"))
 }
 
-struct BinarySearcher<T:Eq+Copy+Display> {
-    min: usize, max: usize, lo: T, hi: T
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(5, 1, solve_1);
+    registry.add(5, 2, solve_2);
+}
+
+// Each pass is a row code of `F`/`B` bits followed by a column code of
+// `L`/`R` bits, most significant first; the seat id concatenates the two.
+#[derive(Debug,Eq,PartialEq,Clone,Copy)]
+pub struct PassCodec {
+    row_bits: usize,
+    col_bits: usize
 }
 
-impl <T:Eq+Copy+Display> BinarySearcher<T> {
-    pub fn test(&mut self, test_val: T) {
-        let mid = (self.min + self.max) / 2;
-        if test_val == self.lo {
-            self.max = mid;
-        } else if test_val == self.hi {
-            self.min = mid + 1;
+// A `SeatMap` holds one flag per possible seat id, so keep that to about a million seats.
+const MAX_BITS: usize = 20;
+
+impl Default for PassCodec {
+    fn default() -> Self {
+        PassCodec {row_bits: 7, col_bits: 3}
+    }
+}
+
+impl PassCodec {
+    pub fn new(row_bits: usize, col_bits: usize) -> Result<Self,String> {
+        if row_bits + col_bits == 0 || row_bits + col_bits > MAX_BITS {
+            Err(format!("Passes need between 1 and {} bits, not {}", MAX_BITS, row_bits + col_bits))
         } else {
-            panic!("Illegal value: {}", test_val);
+            Ok(PassCodec {row_bits, col_bits})
         }
     }
 
-    pub fn value(&self) -> usize {
-        assert!(self.min == self.max);
-        self.min
+    pub fn rows(&self) -> usize {1 << self.row_bits}
+
+    pub fn cols(&self) -> usize {1 << self.col_bits}
+
+    pub fn decode(&self, encoding: &str) -> Result<BoardingPass,String> {
+        let codes: Vec<char> = encoding.chars().collect();
+        if codes.len() != self.row_bits + self.col_bits {
+            return Err(format!("Illegal encoding length {}", codes.len()));
+        }
+        let (row_codes, col_codes) = codes.split_at(self.row_bits);
+        Ok(BoardingPass {row: decode_bits(row_codes, 'F', 'B')?, col: decode_bits(col_codes, 'L', 'R')?})
+    }
+
+    pub fn encode(&self, pass: &BoardingPass) -> Result<String,String> {
+        if pass.row >= self.rows() || pass.col >= self.cols() {
+            Err(format!("Seat ({}, {}) is outside {} rows and {} columns", pass.row, pass.col, self.rows(), self.cols()))
+        } else {
+            Ok(encode_bits(pass.row, self.row_bits, 'F', 'B') + encode_bits(pass.col, self.col_bits, 'L', 'R').as_str())
+        }
+    }
+
+    pub fn seat_id(&self, pass: &BoardingPass) -> usize {
+        pass.row << self.col_bits | pass.col
+    }
+
+    pub fn pass_for(&self, seat_id: usize) -> Result<BoardingPass,String> {
+        if seat_id >= self.rows() * self.cols() {
+            Err(format!("Seat id {} is outside {} rows and {} columns", seat_id, self.rows(), self.cols()))
+        } else {
+            Ok(BoardingPass {row: seat_id >> self.col_bits, col: seat_id & (self.cols() - 1)})
+        }
     }
-}
 
-fn decode_str(encoding: &str, lo: char, hi: char) -> usize {
-    let encoding = encoding.as_bytes();
-    let mut searcher = BinarySearcher  {min: 0, max: (1 << encoding.len()) - 1, lo: lo as u8, hi: hi as u8};
-    for code in encoding.iter() {
-        searcher.test(*code);
+    pub fn encode_id(&self, seat_id: usize) -> Result<String,String> {
+        self.encode(&self.pass_for(seat_id)?)
     }
-    searcher.value()
+}
+
+fn decode_bits(codes: &[char], zero: char, one: char) -> Result<usize,String> {
+    codes.iter().try_fold(0, |value, code| match *code {
+        c if c == zero => Ok(value << 1),
+        c if c == one => Ok(value << 1 | 1),
+        c => Err(format!("Illegal value: {}", c))
+    })
+}
+
+fn encode_bits(value: usize, bits: usize, zero: char, one: char) -> String {
+    (0..bits).rev()
+        .map(|bit| if value >> bit & 1 == 1 {one} else {zero})
+        .collect()
 }
 
 #[derive(Debug,Eq,PartialEq,Clone,Copy)]
@@ -60,16 +116,8 @@ pub struct BoardingPass {
 }
 
 impl BoardingPass {
-    pub fn from(encoding: &str) -> Self {
-        if encoding.len() == 10 {
-            BoardingPass {
-                row: decode_str(&encoding[..7], 'F', 'B'),
-                col: decode_str(&encoding[7..], 'L', 'R') }
-        } else {panic!("Illegal encoding length {}: {}", encoding.len(), encoding)}
-    }
-
-    pub fn seat_id(&self) -> usize {
-        self.row * 8 + self.col
+    pub fn from(encoding: &str) -> Result<Self,String> {
+        PassCodec::default().decode(encoding)
     }
 
     pub fn row(&self) -> usize {self.row}
@@ -77,15 +125,108 @@ impl BoardingPass {
     pub fn col(&self) -> usize {self.col}
 }
 
+// Seats are indexed by seat id, so rows run front to back and each row
+// left to right.
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct SeatMap {
+    codec: PassCodec,
+    occupied: Vec<bool>
+}
+
+impl SeatMap {
+    pub fn new(codec: PassCodec, passes: &[BoardingPass]) -> Result<Self,String> {
+        let mut map = SeatMap::empty(codec);
+        for pass in passes.iter() {
+            map.take(pass)?;
+        }
+        Ok(map)
+    }
+
+    fn empty(codec: PassCodec) -> Self {
+        SeatMap {codec, occupied: vec![false; codec.rows() * codec.cols()]}
+    }
+
+    fn take(&mut self, pass: &BoardingPass) -> Result<(),String> {
+        self.codec.encode(pass)?;
+        let id = self.codec.seat_id(pass);
+        if self.occupied[id] {
+            return Err(format!("Seat id {} appears on more than one pass", id));
+        }
+        self.occupied[id] = true;
+        Ok(())
+    }
+
+    pub fn from_file(filename: &str, codec: PassCodec) -> AdventResult<Self> {
+        let mut map = SeatMap::empty(codec);
+        for (line_num, line) in numbered_lines(filename)? {
+            codec.decode(line.as_str())
+                .and_then(|pass| map.take(&pass))
+                .at_line(DAY, line_num, line.as_str())?;
+        }
+        Ok(map)
+    }
+
+    // Seats outside the plane are never occupied.
+    pub fn is_occupied(&self, row: usize, col: usize) -> bool {
+        row < self.codec.rows() && col < self.codec.cols() && self.occupied[row * self.codec.cols() + col]
+    }
+
+    fn row_occupied(&self, row: usize) -> bool {
+        (0..self.codec.cols()).any(|col| self.is_occupied(row, col))
+    }
+
+    // One line per row, `#` for a taken seat and `.` for an empty one.
+    pub fn render(&self) -> String {
+        self.occupied.chunks(self.codec.cols())
+            .map(|row| row.iter().map(|taken| if *taken {'#'} else {'.'}).collect::<String>() + "\n")
+            .collect()
+    }
+
+    pub fn empty_seats(&self) -> Vec<usize> {
+        (0..self.occupied.len()).filter(|id| !self.occupied[*id]).collect()
+    }
+
+    // Maximal runs of consecutive empty seat ids.
+    pub fn empty_blocks(&self) -> Vec<RangeInclusive<usize>> {
+        let mut blocks: Vec<RangeInclusive<usize>> = Vec::new();
+        for id in self.empty_seats() {
+            match blocks.last_mut() {
+                Some(block) if *block.end() + 1 == id => *block = *block.start()..=id,
+                _ => blocks.push(id..=id)
+            }
+        }
+        blocks
+    }
+
+    // Empty seats with a taken seat on each side.
+    pub fn single_gaps(&self) -> Vec<usize> {
+        self.empty_blocks().iter()
+            .filter(|block| block.start() == block.end() && *block.start() > 0 && *block.end() + 1 < self.occupied.len())
+            .map(|block| *block.start())
+            .collect()
+    }
+
+    // Rows with no taken seats before the first occupied row.
+    pub fn missing_front_rows(&self) -> Range<usize> {
+        0..(0..self.codec.rows()).find(|row| self.row_occupied(*row)).unwrap_or(self.codec.rows())
+    }
+
+    // Rows with no taken seats after the last occupied row.
+    pub fn missing_back_rows(&self) -> Range<usize> {
+        (0..self.codec.rows()).rev().find(|row| self.row_occupied(*row)).map_or(0, |row| row + 1)..self.codec.rows()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     fn test(encoding: &str, row: usize, col: usize, id: usize) {
-        let pass = BoardingPass::from(encoding);
+        let pass = BoardingPass::from(encoding).unwrap();
         assert_eq!(pass.row(), row);
         assert_eq!(pass.col(), col);
-        assert_eq!(pass.seat_id(), id);
+        assert_eq!(PassCodec::default().seat_id(&pass), id);
     }
 
     #[test]
@@ -107,4 +248,89 @@ mod tests {
     fn test_decode_3() {
         test("BBFFBBFRLL", 102, 4, 820);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_decode_errors() {
+        assert_eq!(BoardingPass::from("FBFBBFFRL").unwrap_err(), "Illegal encoding length 9");
+        assert_eq!(BoardingPass::from("FBFBBFFRLX").unwrap_err(), "Illegal value: X");
+        assert_eq!(BoardingPass::from("FBFBBFLRLR").unwrap_err(), "Illegal value: L");
+    }
+
+    #[test]
+    fn test_encode() {
+        let codec = PassCodec::default();
+        for (encoding, id) in [("FBFBBFFRLR", 357), ("BFFFBBFRRR", 567), ("FFFBBBFRRR", 119), ("BBFFBBFRLL", 820)].iter() {
+            assert_eq!(codec.encode_id(*id).unwrap(), *encoding);
+            assert_eq!(codec.encode(&BoardingPass::from(encoding).unwrap()).unwrap(), *encoding);
+        }
+        assert_eq!(codec.encode_id(1024).unwrap_err(), "Seat id 1024 is outside 128 rows and 8 columns");
+    }
+
+    #[test]
+    fn test_plane_sizes() {
+        let codec = PassCodec::new(4, 2).unwrap();
+        let pass = codec.decode("BFFBRL").unwrap();
+        assert_eq!((pass.row(), pass.col(), codec.seat_id(&pass)), (9, 2, 38));
+        assert_eq!(codec.encode_id(38).unwrap(), "BFFBRL");
+        assert_eq!(codec.decode("FBFBBFFRLR").unwrap_err(), "Illegal encoding length 10");
+        assert_eq!(PassCodec::default().encode(&pass).unwrap(), "FFFBFFBLRL");
+        assert_eq!(codec.encode(&BoardingPass::from("FBFBBFFRLR").unwrap()).unwrap_err(),
+                   "Seat (44, 5) is outside 16 rows and 4 columns");
+        assert_eq!(PassCodec::new(15, 6).unwrap_err(), "Passes need between 1 and 20 bits, not 21");
+        assert_eq!(PassCodec::new(14, 6).unwrap().rows(), 16384);
+        assert_eq!(PassCodec::new(0, 3).unwrap().encode_id(6).unwrap(), "RRL");
+    }
+
+    fn small_plane() -> SeatMap {
+        let codec = PassCodec::new(3, 2).unwrap();
+        let passes: Vec<BoardingPass> = [9, 10, 12, 13, 15, 16, 18, 19]
+            .iter()
+            .map(|id| codec.pass_for(*id).unwrap())
+            .collect();
+        SeatMap::new(codec, &passes).unwrap()
+    }
+
+    #[test]
+    fn test_seat_map() {
+        let map = small_plane();
+        assert_eq!(map.render(), "\
+....
+....
+.##.
+##.#
+#.##
+....
+....
+....
+");
+        assert!(map.is_occupied(2, 1) && !map.is_occupied(3, 2));
+        assert!(map.is_occupied(3, 0) && !map.is_occupied(2, 4) && !map.is_occupied(8, 0));
+        assert_eq!(map.empty_seats().len(), 24);
+        assert_eq!(map.empty_blocks(), vec![0..=8, 11..=11, 14..=14, 17..=17, 20..=31]);
+        assert_eq!(map.single_gaps(), vec![11, 14, 17]);
+        assert_eq!(map.missing_front_rows(), 0..2);
+        assert_eq!(map.missing_back_rows(), 5..8);
+    }
+
+    #[test]
+    fn test_empty_map() {
+        let map = SeatMap::new(PassCodec::new(2, 1).unwrap(), &[]).unwrap();
+        assert_eq!(map.empty_blocks(), vec![0..=7]);
+        assert_eq!(map.single_gaps(), Vec::<usize>::new());
+        assert_eq!((map.missing_front_rows(), map.missing_back_rows()), (0..4, 0..4));
+        assert_eq!(SeatMap::new(PassCodec::new(2, 1).unwrap(), &[BoardingPass::from("FFFFBBBLLR").unwrap()]).unwrap_err(),
+                   "Seat (7, 1) is outside 4 rows and 2 columns");
+    }
+
+    #[test]
+    fn test_solve_2() {
+        let input = InputFile::from_reader("FFFBBFFRLL\nFFFBBFFRRL\nFFFBBFFRRR\n".as_bytes()).unwrap();
+        assert_eq!(solve_2(input.filename()).unwrap(), "101");
+        let input = InputFile::from_reader("FFFBBFFRLL\nFFFBBFFRLR\n".as_bytes()).unwrap();
+        assert_eq!(solve_2(input.filename()).unwrap_err().to_string(),
+                   "day 5: no solution: no single-seat gap between boarding passes");
+        let input = InputFile::from_reader("FFFBBFFRLL\nFFFBBFFRRR\nFFFBBFFRLL\n".as_bytes()).unwrap();
+        assert_eq!(solve_2(input.filename()).unwrap_err().to_string(),
+                   "day 5, line 3: Seat id 100 appears on more than one pass in `FFFBBFFRLL`");
+    }
+}
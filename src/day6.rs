@@ -1,83 +1,237 @@
-use std::io;
 use advent_code_lib::{MultiLineObjects, ExNihilo};
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::Display;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::AdventResult;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
-    let answers = MultiLineObjects::from_file
-        (filename,
-        |set: &mut BTreeSet<char>, line| {
-            for c in line.chars() {
-                set.insert(c);
-            }
-        })?;
-    Ok(answers.iter().map(|s| s.len()).sum::<usize>().to_string())
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    Ok(total(filename, Aggregate::Union)?.to_string())
+}
+
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    Ok(total(filename, Aggregate::Intersection)?.to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(6, 1, solve_1);
+    registry.add(6, 2, solve_2);
+}
+
+pub fn groups(filename: &str) -> AdventResult<Vec<GroupAnswers>> {
     let answers = MultiLineObjects::from_file
         (filename,
-        |p2g: &mut Puzzle2Group, line: &str| {
-            p2g.apply_line(line);
+        |group: &mut GroupAnswers, line: &str| {
+            group.add_member(line);
         })?;
-    Ok(answers.iter().map(|p2g| p2g.num_selected()).sum::<usize>().to_string())
+    Ok(answers.objects())
+}
+
+pub fn total(filename: &str, aggregate: Aggregate) -> AdventResult<usize> {
+    Ok(total_of(&groups(filename)?, aggregate))
+}
+
+pub fn total_of(groups: &[GroupAnswers], aggregate: Aggregate) -> usize {
+    groups.iter().map(|g| g.aggregate(aggregate).len()).sum()
 }
 
-#[derive(Clone,Eq,PartialEq)]
-pub struct Puzzle2Group {
-    selected_chars: BTreeSet<char>
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Aggregate {
+    Union,
+    Intersection,
+    AtLeast(usize),
+    // Answered by an odd number of members.
+    SymmetricDifference
 }
 
-fn i2letter(i: u8) -> char {
-    (i + 'a' as u8) as char
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct GroupAnswers {
+    members: Vec<BTreeSet<char>>
 }
 
-impl ExNihilo for Puzzle2Group {
+impl ExNihilo for GroupAnswers {
     fn create() -> Self {
-        Puzzle2Group {selected_chars: (0..26).map(|i| i2letter(i)).collect()}
+        GroupAnswers {members: Vec::new()}
     }
 }
 
-impl Puzzle2Group {
-    pub fn apply_line(&mut self, line: &str) {
-        self.selected_chars = self.selected_chars.iter().copied()
-            .filter(|c| line.contains(*c))
-            .collect();
+impl GroupAnswers {
+    pub fn add_member(&mut self, line: &str) {
+        self.members.push(line.trim().chars().collect());
     }
 
-    pub fn num_selected(&self) -> usize {self.selected_chars.len()}
+    pub fn num_members(&self) -> usize {self.members.len()}
 
-    pub fn chars_selected(&self) -> String {
-        self.selected_chars.iter().collect()
+    pub fn members(&self) -> &[BTreeSet<char>] {&self.members}
+
+    pub fn counts(&self) -> BTreeMap<char,usize> {
+        let mut counts = BTreeMap::new();
+        for c in self.members.iter().flatten() {
+            *counts.entry(*c).or_insert(0) += 1;
+        }
+        counts
+    }
+
+    pub fn aggregate(&self, aggregate: Aggregate) -> BTreeSet<char> {
+        let keep = |count: usize| match aggregate {
+            Aggregate::Union => count > 0,
+            Aggregate::Intersection => count == self.num_members(),
+            Aggregate::AtLeast(k) => count >= k,
+            Aggregate::SymmetricDifference => count % 2 == 1
+        };
+        self.counts().iter()
+            .filter(|(_, count)| keep(**count))
+            .map(|(c, _)| *c)
+            .collect()
     }
 }
 
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct AnswerStats {
+    pub groups: usize,
+    pub people: usize,
+    pub counts: BTreeMap<char,usize>,
+    pub group_sizes: BTreeMap<usize,usize>
+}
+
+impl AnswerStats {
+    pub fn from(groups: &[GroupAnswers]) -> Self {
+        let mut counts = BTreeMap::new();
+        let mut group_sizes = BTreeMap::new();
+        for group in groups.iter() {
+            for (c, count) in group.counts() {
+                *counts.entry(c).or_insert(0) += count;
+            }
+            *group_sizes.entry(group.num_members()).or_insert(0) += 1;
+        }
+        AnswerStats {groups: groups.len(), people: groups.iter().map(|g| g.num_members()).sum(), counts, group_sizes}
+    }
+
+    pub fn most_common(&self) -> (Vec<char>, usize) {
+        extreme(&self.counts, |counts| counts.max())
+    }
+
+    // Only questions somebody answered are considered.
+    pub fn least_common(&self) -> (Vec<char>, usize) {
+        extreme(&self.counts, |counts| counts.min())
+    }
+}
+
+fn extreme<F: Fn(&mut dyn Iterator<Item=usize>) -> Option<usize>>(counts: &BTreeMap<char,usize>, pick: F) -> (Vec<char>, usize) {
+    let target = pick(&mut counts.values().copied()).unwrap_or(0);
+    (counts.iter().filter(|(_, count)| **count == target).map(|(c, _)| *c).collect(), target)
+}
+
+fn counts_line(stats: &AnswerStats) -> String {
+    let (most, most_count) = stats.most_common();
+    let (least, least_count) = stats.least_common();
+    format!("{}; most common {} ({}); least common {} ({})",
+            stats.counts.iter().map(|(c, n)| format!("{} {}", c, n)).collect::<Vec<_>>().join(", "),
+            most.iter().collect::<String>(), most_count, least.iter().collect::<String>(), least_count)
+}
+
+const BAR_WIDTH: usize = 50;
+
+// Bars are scaled so the longest is `BAR_WIDTH` wide; any nonzero count gets at least one `#`.
+fn histogram<K: Display>(counts: &BTreeMap<K,usize>) -> String {
+    let max_count = counts.values().copied().max().unwrap_or(1);
+    counts.iter()
+        .map(|(key, count)| format!("{} {} {}\n", key, "#".repeat((count * BAR_WIDTH + max_count - 1) / max_count), count))
+        .collect()
+}
+
+pub fn answer_report(filename: &str) -> AdventResult<String> {
+    let groups = groups(filename)?;
+    let overall = AnswerStats::from(&groups);
+    let mut report = format!("{} groups, {} people\n", overall.groups, overall.people);
+    report.push_str("answers by question:\n");
+    report.push_str(histogram(&overall.counts).as_str());
+    report.push_str("groups by size:\n");
+    report.push_str(histogram(&overall.group_sizes).as_str());
+    report.push_str(format!("overall: {}\n", counts_line(&overall)).as_str());
+    for (i, group) in groups.iter().enumerate() {
+        let stats = AnswerStats::from(std::slice::from_ref(group));
+        report.push_str(format!("group {} (size {}): {}\n", i + 1, stats.people, counts_line(&stats)).as_str());
+    }
+    Ok(report)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
     #[test]
     fn test_1() {
-        assert_eq!(solve_1("in/day6_ex.txt").unwrap(), "11");
+        assert_eq!(solve_1("fixtures/day6_ex.txt").unwrap(), "11");
+    }
+
+    fn selected(group: &GroupAnswers, aggregate: Aggregate) -> String {
+        group.aggregate(aggregate).iter().collect()
+    }
+
+    #[test]
+    fn test_intersection() {
+        let mut group = GroupAnswers::create();
+        group.add_member("abc");
+        assert_eq!(selected(&group, Aggregate::Intersection), "abc");
+        group.add_member("bcd");
+        assert_eq!(selected(&group, Aggregate::Intersection), "bc");
+        group.add_member("cde");
+        assert_eq!(selected(&group, Aggregate::Intersection), "c");
+        group.add_member("def");
+        assert_eq!(selected(&group, Aggregate::Intersection), "");
+        assert_eq!(selected(&GroupAnswers::create(), Aggregate::Intersection), "");
     }
 
     #[test]
-    fn test_p2_group() {
-        let mut p2 = Puzzle2Group::create();
-        p2.apply_line("abc");
-        assert_eq!(p2.num_selected(), 3);
-        assert_eq!(p2.chars_selected(), "abc");
-        p2.apply_line("bcd");
-        assert_eq!(p2.num_selected(), 2);
-        assert_eq!(p2.chars_selected(), "bc");
-        p2.apply_line("cde");
-        assert_eq!(p2.num_selected(), 1);
-        assert_eq!(p2.chars_selected(), "c");
-        p2.apply_line("def");
-        assert_eq!(p2.num_selected(), 0);
+    fn test_aggregates() {
+        let mut group = GroupAnswers::create();
+        for member in ["abc", "bcd", "cde", "def"].iter() {
+            group.add_member(member);
+        }
+        assert_eq!(group.num_members(), 4);
+        assert_eq!(selected(&group, Aggregate::Union), "abcdef");
+        assert_eq!(selected(&group, Aggregate::AtLeast(2)), "bcde");
+        assert_eq!(selected(&group, Aggregate::AtLeast(3)), "cd");
+        assert_eq!(selected(&group, Aggregate::SymmetricDifference), "acdf");
+        assert_eq!(total("fixtures/day6_ex.txt", Aggregate::AtLeast(2)).unwrap(), 2);
+        assert_eq!(total("fixtures/day6_ex.txt", Aggregate::SymmetricDifference).unwrap(), 9);
     }
 
     #[test]
     fn test_2() {
-        assert_eq!(solve_2("in/day6_ex.txt").unwrap(), "6");
+        assert_eq!(solve_2("fixtures/day6_ex.txt").unwrap(), "6");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_stats() {
+        let stats = AnswerStats::from(&groups("fixtures/day6_ex.txt").unwrap());
+        assert_eq!((stats.groups, stats.people), (5, 11));
+        assert_eq!(stats.counts, btreemap!('a' => 8, 'b' => 4, 'c' => 3));
+        assert_eq!(stats.group_sizes, btreemap!(1 => 2, 2 => 1, 3 => 1, 4 => 1));
+        assert_eq!(stats.most_common(), (vec!['a'], 8));
+        assert_eq!(stats.least_common(), (vec!['c'], 3));
+        assert_eq!(AnswerStats::from(&[]).most_common(), (vec![], 0));
+    }
+
+    #[test]
+    fn test_answer_report() {
+        assert_eq!(answer_report("fixtures/day6_ex.txt").unwrap(), "\
+5 groups, 11 people
+answers by question:
+a ################################################## 8
+b ######################### 4
+c ################### 3
+groups by size:
+1 ################################################## 2
+2 ######################### 1
+3 ######################### 1
+4 ######################### 1
+overall: a 8, b 4, c 3; most common a (8); least common c (3)
+group 1 (size 1): a 1, b 1, c 1; most common abc (1); least common abc (1)
+group 2 (size 3): a 1, b 1, c 1; most common abc (1); least common abc (1)
+group 3 (size 2): a 2, b 1, c 1; most common a (2); least common bc (1)
+group 4 (size 4): a 4; most common a (4); least common a (4)
+group 5 (size 1): b 1; most common b (1); least common b (1)
+");
+    }
+}
@@ -1,57 +1,76 @@
-use advent_code_lib::for_each_line;
-use std::io;
 use std::collections::{BTreeSet, BTreeMap};
 use std::collections::btree_map::Keys;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
+const DAY: usize = 7;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
     let graph = create_graph_from(filename)?;
-    let total = graph.all_node_names()
-        .filter(|name| graph.all_successors_of(name).contains("shiny gold"))
-        .count();
-    Ok(total.to_string())
+    Ok(graph.all_predecessors_of("shiny gold").len().to_string())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
     let graph = create_graph_from(filename)?;
-    Ok(graph.bags_within("shiny gold").to_string())
+    graph.bags_within("shiny gold")
+        .map(|count| count.to_string())
+        .map_err(|e| AdventError::no_solution(DAY, e.as_str()))
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(7, 1, solve_1);
+    registry.add(7, 2, solve_2);
 }
 
-fn create_graph_from(filename: &str) -> io::Result<StringGraph> {
+pub fn create_graph_from(filename: &str) -> AdventResult<StringGraph> {
     let mut graph = StringGraph::new();
-    for_each_line(filename, |line| Ok({
-        let left_right: Vec<&str> = line.split("contain ").collect();
-        let key = bag_color(left_right[0]);
-        if !left_right[1].contains("no other bags") {
-            left_right[1].split(", ")
-                .map(|s| bag_count_and_color(s))
-                .for_each(|(count, color)| {
-                    graph.add_edge(key.as_str(), color.as_str(), count);
-                });
-        }
-    }))?;
+    for (line_num, line) in numbered_lines(filename)? {
+        add_rule(&mut graph, line.as_str()).at_line(DAY, line_num, line.as_str())?;
+    }
     Ok(graph)
 }
 
+pub fn add_rule(graph: &mut StringGraph, line: &str) -> Result<(),String> {
+    let left_right: Vec<&str> = line.split("contain ").collect();
+    if left_right.len() != 2 {
+        return Err("expected `<color> bags contain <contents>`".to_string());
+    }
+    let key = bag_color(left_right[0]);
+    if !left_right[1].contains("no other bags") {
+        for s in left_right[1].split(", ") {
+            let (count, color) = bag_count_and_color(s)?;
+            graph.add_edge(key.as_str(), color.as_str(), count);
+        }
+    }
+    Ok(())
+}
+
 fn bag_color(bag_src: &str) -> String {
     bag_src.split(" bag").next().unwrap().to_string()
 }
 
-fn bag_count_and_color(bag_src: &str) -> (usize, String) {
+fn bag_count_and_color(bag_src: &str) -> Result<(usize, String),String> {
     let bag_parts: Vec<&str> = bag_src.splitn(2, ' ').collect();
-    (bag_parts[0].parse::<usize>().unwrap(), bag_color(bag_parts[1]))
+    if bag_parts.len() != 2 {
+        return Err(format!("expected `<count> <color> bags`, found `{}`", bag_src));
+    }
+    Ok((parse_num(bag_parts[0])?, bag_color(bag_parts[1])))
 }
 
 #[derive(Clone,Debug,Eq,Ord,PartialOrd,PartialEq)]
 pub struct StringGraph {
-    node2nodes: BTreeMap<String,BTreeMap<String,usize>>
+    node2nodes: BTreeMap<String,BTreeMap<String,usize>>,
+    node2preds: BTreeMap<String,BTreeMap<String,usize>>
 }
 
 impl StringGraph {
-    pub fn new() -> Self {StringGraph {node2nodes: BTreeMap::new()}}
+    pub fn new() -> Self {StringGraph {node2nodes: BTreeMap::new(), node2preds: BTreeMap::new()}}
 
     pub fn add_if_absent(&mut self, name: &str) {
         if !self.node2nodes.contains_key(name) {
             self.node2nodes.insert(name.to_string(), BTreeMap::new());
+            self.node2preds.insert(name.to_string(), BTreeMap::new());
         }
     }
 
@@ -59,6 +78,7 @@ impl StringGraph {
         self.add_if_absent(start);
         self.add_if_absent(end);
         self.node2nodes.get_mut(start).unwrap().insert(end.to_string(), count);
+        self.node2preds.get_mut(end).unwrap().insert(start.to_string(), count);
     }
 
     pub fn all_node_names(&self) -> Keys<String,BTreeMap<String,usize>> {
@@ -72,35 +92,179 @@ impl StringGraph {
     }
 
     pub fn all_successors_of(&self, name: &str) -> BTreeSet<String> {
-        let mut visited = BTreeSet::new();
-        if self.node2nodes.contains_key(name) {
-            let mut open_list: Vec<String> = self.node2nodes.get(name).unwrap().keys()
-                .map(|s| s.clone())
-                .collect();
-            while open_list.len() > 0 {
-                let candidate = open_list.pop().unwrap();
-                if !visited.contains(candidate.as_str()) {
-                    self.node2nodes.get(candidate.as_str()).unwrap().keys()
-                        .for_each(|s| open_list.push(s.clone()));
-                    visited.insert(candidate);
+        reachable(&self.node2nodes, name)
+    }
+
+    pub fn all_predecessors_of(&self, name: &str) -> BTreeSet<String> {
+        reachable(&self.node2preds, name)
+    }
+
+    // How many `inner` bags end up inside one `outer` bag, over every path.
+    pub fn count_within(&self, outer: &str, inner: &str) -> Result<usize,String> {
+        self.accumulate(outer, &|bag| (bag == inner) as usize, &mut BTreeMap::new(), &mut Vec::new())
+    }
+
+    pub fn bags_within(&self, name: &str) -> Result<usize,String> {
+        self.accumulate(name, &|_| 1, &mut BTreeMap::new(), &mut Vec::new())
+    }
+
+    // Sums `count * (base(bag) + accumulate(bag))` over the bags directly
+    // inside `name`, remembering each bag's total so shared contents are
+    // only counted once; `path` holds the bags currently being expanded.
+    fn accumulate(&self, name: &str, base: &dyn Fn(&str) -> usize, memo: &mut BTreeMap<String,usize>,
+                  path: &mut Vec<String>) -> Result<usize,String> {
+        if let Some(total) = memo.get(name) {
+            return Ok(*total);
+        }
+        if let Some(start) = path.iter().position(|bag| bag == name) {
+            return Err(cycle_message(&path[start..], name));
+        }
+        path.push(name.to_string());
+        let mut total = 0;
+        if let Some(m) = self.node2nodes.get(name) {
+            for (key, value) in m.iter() {
+                total += value * (base(key) + self.accumulate(key, base, memo, path)?);
+            }
+        }
+        path.pop();
+        memo.insert(name.to_string(), total);
+        Ok(total)
+    }
+
+    // Returns a path that starts and ends at the same bag.
+    pub fn find_cycle(&self) -> Option<Vec<String>> {
+        let mut finished = BTreeSet::new();
+        for name in self.all_node_names() {
+            let mut path = Vec::new();
+            if let Some(cycle) = self.cycle_from(name, &mut finished, &mut path) {
+                return Some(cycle);
+            }
+        }
+        None
+    }
+
+    fn cycle_from(&self, name: &str, finished: &mut BTreeSet<String>, path: &mut Vec<String>) -> Option<Vec<String>> {
+        if finished.contains(name) {
+            return None;
+        }
+        if let Some(start) = path.iter().position(|bag| bag == name) {
+            let mut cycle = path[start..].to_vec();
+            cycle.push(name.to_string());
+            return Some(cycle);
+        }
+        path.push(name.to_string());
+        for next in self.node2nodes.get(name).unwrap().keys() {
+            if let Some(cycle) = self.cycle_from(next, finished, path) {
+                return Some(cycle);
+            }
+        }
+        path.pop();
+        finished.insert(name.to_string());
+        None
+    }
+
+    // Nodes are bag colors; each edge is labelled with how many of its end fit in its start.
+    pub fn to_dot(&self, subgraph: Subgraph) -> String {
+        let mut nodes: BTreeSet<String> = match subgraph {
+            Subgraph::Whole => self.all_node_names().cloned().collect(),
+            Subgraph::ReachableFrom(name) => self.all_successors_of(name),
+            Subgraph::LeadingTo(name) => self.all_predecessors_of(name)
+        };
+        if let Subgraph::ReachableFrom(name) | Subgraph::LeadingTo(name) = subgraph {
+            if self.node2nodes.contains_key(name) {
+                nodes.insert(name.to_string());
+            }
+        }
+        let mut dot = String::from("digraph bags {\n");
+        for node in nodes.iter() {
+            dot.push_str(format!("    {};\n", dot_id(node)).as_str());
+        }
+        for start in nodes.iter() {
+            for (end, count) in self.node2nodes.get(start).unwrap().iter().filter(|(end, _)| nodes.contains(*end)) {
+                dot.push_str(format!("    {} -> {} [label=\"{}\"];\n", dot_id(start), dot_id(end), count).as_str());
+            }
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    // Every bag comes before the bags it contains.
+    pub fn topological_order(&self) -> Result<Vec<String>,String> {
+        let mut in_degree: BTreeMap<&String,usize> = self.node2preds.iter()
+            .map(|(name, preds)| (name, preds.len()))
+            .collect();
+        let mut ready: BTreeSet<&String> = in_degree.iter()
+            .filter(|(_, degree)| **degree == 0)
+            .map(|(name, _)| *name)
+            .collect();
+        let mut order = Vec::new();
+        while let Some(name) = ready.pop_first() {
+            for next in self.node2nodes.get(name).unwrap().keys() {
+                let degree = in_degree.get_mut(next).unwrap();
+                *degree -= 1;
+                if *degree == 0 {
+                    ready.insert(next);
                 }
             }
+            order.push(name.clone());
+        }
+        if order.len() == self.node2nodes.len() {
+            Ok(order)
+        } else {
+            let cycle = self.find_cycle().unwrap();
+            Err(cycle_message(&cycle[..cycle.len() - 1], cycle[0].as_str()))
+        }
+    }
+}
+
+#[derive(Debug,Copy,Clone,Eq,PartialEq)]
+pub enum Subgraph<'a> {
+    Whole,
+    ReachableFrom(&'a str),
+    LeadingTo(&'a str)
+}
+
+impl <'a> Subgraph<'a> {
+    // `all`, `from:<color>` or `to:<color>`
+    pub fn parse(spec: &'a str) -> Result<Self,String> {
+        if spec == "all" {
+            Ok(Subgraph::Whole)
+        } else if let Some(name) = spec.strip_prefix("from:") {
+            Ok(Subgraph::ReachableFrom(name))
+        } else if let Some(name) = spec.strip_prefix("to:") {
+            Ok(Subgraph::LeadingTo(name))
+        } else {
+            Err(format!("Unrecognized subgraph `{}`", spec))
         }
-        visited
     }
+}
+
+fn dot_id(name: &str) -> String {
+    format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+}
 
-    pub fn bags_within(&self, name: &str) -> usize {
-        self.node2nodes.get(name)
-            .map_or(0,
-                    |m| m.iter()
-                        .map(|(key,value)| value * (1 + self.bags_within(key)))
-                        .sum())
+fn cycle_message(path: &[String], back_to: &str) -> String {
+    format!("cycle: {} -> {}", path.join(" -> "), back_to)
+}
+
+fn reachable(edges: &BTreeMap<String,BTreeMap<String,usize>>, name: &str) -> BTreeSet<String> {
+    let mut visited = BTreeSet::new();
+    if let Some(neighbors) = edges.get(name) {
+        let mut open_list: Vec<&String> = neighbors.keys().collect();
+        while let Some(candidate) = open_list.pop() {
+            if !visited.contains(candidate) {
+                open_list.extend(edges.get(candidate).unwrap().keys());
+                visited.insert(candidate.clone());
+            }
+        }
     }
+    visited
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     pub fn test_string_graph() {
@@ -115,6 +279,120 @@ mod tests {
             });
     }
 
+    #[test]
+    pub fn test_predecessors() {
+        let mut sg = StringGraph::new();
+        [("a", "b"), ("b", "c"), ("c", "d"), ("b", "e")].iter()
+            .for_each(|(a, b)| sg.add_edge(*a, *b, 1));
+        [("a", btreeset!()), ("b", btreeset!("a")), ("c", btreeset!("a", "b")),
+            ("d", btreeset!("a", "b", "c")), ("e", btreeset!("a", "b")), ("z", btreeset!())].iter()
+            .for_each(|(k, s)| {
+                assert_eq!(sg.all_predecessors_of(k),
+                           s.iter().map(|s| s.to_string()).collect::<BTreeSet<String>>());
+            });
+    }
+
+    #[test]
+    pub fn test_count_within() {
+        let graph = create_graph_from("fixtures/day7_ex1.txt").unwrap();
+        [("shiny gold", "faded blue", 13), ("shiny gold", "dotted black", 16), ("light red", "shiny gold", 5),
+            ("light red", "faded blue", 83), ("faded blue", "shiny gold", 0), ("shiny gold", "shiny gold", 0)].iter()
+            .for_each(|(outer, inner, count)| {
+                assert_eq!(graph.count_within(outer, inner).unwrap(), *count as usize);
+            });
+    }
+
+    fn cyclic_graph() -> StringGraph {
+        let mut sg = StringGraph::new();
+        [("d", "a"), ("a", "b"), ("b", "c"), ("c", "a"), ("b", "e")].iter()
+            .for_each(|(a, b)| sg.add_edge(*a, *b, 2));
+        sg
+    }
+
+    #[test]
+    pub fn test_find_cycle() {
+        assert_eq!(cyclic_graph().find_cycle(), Some(vec!["a".to_string(), "b".to_string(), "c".to_string(), "a".to_string()]));
+        assert_eq!(create_graph_from("fixtures/day7_ex1.txt").unwrap().find_cycle(), None);
+        let mut sg = StringGraph::new();
+        sg.add_edge("x", "x", 1);
+        assert_eq!(sg.find_cycle(), Some(vec!["x".to_string(), "x".to_string()]));
+        assert_eq!(sg.bags_within("x").unwrap_err(), "cycle: x -> x");
+    }
+
+    #[test]
+    pub fn test_topological_order() {
+        let graph = create_graph_from("fixtures/day7_ex1.txt").unwrap();
+        let order = graph.topological_order().unwrap();
+        assert_eq!(order.len(), 9);
+        for start in order.iter() {
+            for end in graph.all_successors_of(start) {
+                assert!(order.iter().position(|n| n == start) < order.iter().position(|n| *n == end));
+            }
+        }
+        assert_eq!(cyclic_graph().topological_order().unwrap_err(), "cycle: a -> b -> c -> a");
+    }
+
+    #[test]
+    pub fn test_cyclic_counts() {
+        let graph = cyclic_graph();
+        assert_eq!(graph.bags_within("e").unwrap(), 0);
+        assert_eq!(graph.bags_within("d").unwrap_err(), "cycle: a -> b -> c -> a");
+        assert_eq!(graph.count_within("b", "d").unwrap_err(), "cycle: b -> c -> a -> b");
+        let input = InputFile::from_reader("\
+shiny gold bags contain 2 dark red bags.
+dark red bags contain 1 shiny gold bag.
+".as_bytes()).unwrap();
+        assert_eq!(solve_2(input.filename()).unwrap_err().to_string(),
+                   "day 7: no solution: cycle: shiny gold -> dark red -> shiny gold");
+    }
+
+    #[test]
+    pub fn test_shared_contents() {
+        let mut sg = StringGraph::new();
+        for level in 0..60 {
+            sg.add_edge(level.to_string().as_str(), (level + 1).to_string().as_str(), 1);
+            sg.add_edge(level.to_string().as_str(), format!("{}'", level + 1).as_str(), 1);
+            sg.add_edge(format!("{}'", level).as_str(), (level + 1).to_string().as_str(), 1);
+            sg.add_edge(format!("{}'", level).as_str(), format!("{}'", level + 1).as_str(), 1);
+        }
+        assert_eq!(sg.count_within("0", "60").unwrap(), 1 << 59);
+    }
+
+    #[test]
+    pub fn test_to_dot() {
+        let graph = create_graph_from("fixtures/day7_ex1.txt").unwrap();
+        assert_eq!(graph.to_dot(Subgraph::parse("from:shiny gold").unwrap()), "\
+digraph bags {
+    \"dark olive\";
+    \"dotted black\";
+    \"faded blue\";
+    \"shiny gold\";
+    \"vibrant plum\";
+    \"dark olive\" -> \"dotted black\" [label=\"4\"];
+    \"dark olive\" -> \"faded blue\" [label=\"3\"];
+    \"shiny gold\" -> \"dark olive\" [label=\"1\"];
+    \"shiny gold\" -> \"vibrant plum\" [label=\"2\"];
+    \"vibrant plum\" -> \"dotted black\" [label=\"6\"];
+    \"vibrant plum\" -> \"faded blue\" [label=\"5\"];
+}
+");
+        assert_eq!(graph.to_dot(Subgraph::LeadingTo("bright white")), "\
+digraph bags {
+    \"bright white\";
+    \"dark orange\";
+    \"light red\";
+    \"dark orange\" -> \"bright white\" [label=\"3\"];
+    \"light red\" -> \"bright white\" [label=\"1\"];
+}
+");
+        let whole = graph.to_dot(Subgraph::Whole);
+        assert_eq!(whole.lines().filter(|line| line.contains(" -> ")).count(), 13);
+        assert_eq!(whole.lines().count(), 9 + 13 + 2);
+        assert_eq!(graph.to_dot(Subgraph::ReachableFrom("plaid")), "digraph bags {\n}\n");
+        assert_eq!(Subgraph::parse("near:shiny gold").unwrap_err(), "Unrecognized subgraph `near:shiny gold`");
+        assert_eq!(dot_id("say \"hi\""), "\"say \\\"hi\\\"\"");
+    }
+
     #[test]
     pub fn test_bag_color() {
         [("light red bags", "light red"), ("bright white bag", "bright white"),
@@ -134,15 +412,25 @@ mod tests {
             ("2 shiny gold bags", 2, "shiny gold"),
             ("9 faded blue bags", 9, "faded blue")].iter()
             .for_each(|(src, count, color)| {
-                let (ct, cl) = bag_count_and_color(src);
+                let (ct, cl) = bag_count_and_color(src).unwrap();
                 assert_eq!(cl.as_str(), *color);
                 assert_eq!(ct, *count as usize);
             });
     }
 
+    #[test]
+    pub fn test_bad_rules() {
+        let mut graph = StringGraph::new();
+        assert_eq!(add_rule(&mut graph, "light red bags hold 1 bright white bag.").unwrap_err(),
+                   "expected `<color> bags contain <contents>`");
+        assert_eq!(add_rule(&mut graph, "light red bags contain one bright white bag.").unwrap_err(),
+                   "`one` is not a number");
+        assert_eq!(bag_count_and_color("3").unwrap_err(), "expected `<count> <color> bags`, found `3`");
+    }
+
     #[test]
     pub fn test_create_example() {
-        let graph = create_graph_from("in/day7_ex1.txt").unwrap();
+        let graph = create_graph_from("fixtures/day7_ex1.txt").unwrap();
         [("light red", "bright white", 1),
             ("light red", "muted yellow", 2),
             ("dark orange", "bright white", 3),
@@ -162,26 +450,26 @@ mod tests {
 
     #[test]
     pub fn test_solve_1() {
-        assert_eq!(solve_1("in/day7_ex1.txt").unwrap(), "4");
+        assert_eq!(solve_1("fixtures/day7_ex1.txt").unwrap(), "4");
     }
 
     #[test]
     pub fn test_bag_sum() {
-        let graph = create_graph_from("in/day7_ex1.txt").unwrap();
+        let graph = create_graph_from("fixtures/day7_ex1.txt").unwrap();
         [("faded blue", 0), ("dotted black", 0), ("vibrant plum", 11), ("dark olive", 7),
             ("shiny gold", 32), ("muted yellow", 75)].iter()
             .for_each(|(color, count)| {
-                assert_eq!(graph.bags_within(color), *count as usize)
+                assert_eq!(graph.bags_within(color).unwrap(), *count as usize)
             });
     }
 
     #[test]
     pub fn test_solve_2_1() {
-        assert_eq!(solve_2("in/day7_ex1.txt").unwrap(), "32");
+        assert_eq!(solve_2("fixtures/day7_ex1.txt").unwrap(), "32");
     }
 
     #[test]
     pub fn test_solve_2_2() {
-        assert_eq!(solve_2("in/day7_ex2.txt").unwrap(), "126");
+        assert_eq!(solve_2("fixtures/day7_ex2.txt").unwrap(), "126");
     }
 }
\ No newline at end of file
@@ -1,10 +1,13 @@
-use std::io;
 use crate::day8::Instruction::{Nop, Acc, Jmp};
-use advent_code_lib::all_lines;
 use std::collections::BTreeSet;
+use crate::puzzle::PuzzleRegistry;
+use crate::error::{AdventResult, AdventError, LineContext, parse_num};
+use crate::input::numbered_lines;
 
-pub fn solve_1(filename: &str) -> io::Result<String> {
-    Ok(terminates(CPUState::from_file(filename)).1.to_string())
+const DAY: usize = 8;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    Ok(terminates(CPUState::from_file(filename)?).1.to_string())
 }
 
 pub fn terminates(mut program: CPUState) -> (bool, isize) {
@@ -16,17 +19,25 @@ pub fn terminates(mut program: CPUState) -> (bool, isize) {
     (program.terminated(), program.acc())
 }
 
-pub fn solve_2(filename: &str) -> io::Result<String> {
-    let original_program = CPUState::from_file(filename);
-    for i in 0..original_program.len() {
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    repair(&CPUState::from_file(filename)?)
+        .map(|acc_value| acc_value.to_string())
+        .ok_or_else(|| AdventError::no_solution(DAY, "program cannot be fixed"))
+}
+
+// The accumulator of the first single-instruction fix that terminates.
+pub fn repair(original_program: &CPUState) -> Option<isize> {
+    (0..original_program.len()).find_map(|i| {
         let mut fixed_copy = original_program.clone();
         fixed_copy.fix_instr(i);
         let (is_fixed, acc_value) = terminates(fixed_copy);
-        if is_fixed {
-            return Ok(acc_value.to_string());
-        }
-    }
-    Ok("program cannot be fixed".to_owned())
+        if is_fixed {Some(acc_value)} else {None}
+    })
+}
+
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(8, 1, solve_1);
+    registry.add(8, 2, solve_2);
 }
 
 #[derive(Debug,Copy,Clone,Eq,PartialEq)]
@@ -35,14 +46,17 @@ pub enum Instruction {
 }
 
 impl Instruction {
-    pub fn from(text: &str) -> Self {
+    pub fn from(text: &str) -> Result<Self,String> {
         let parts: Vec<&str> = text.split_whitespace().collect();
-        let arg = parts[1].parse::<isize>().unwrap();
+        if parts.len() != 2 {
+            return Err("expected `<op> <arg>`".to_string());
+        }
+        let arg = parse_num::<isize>(parts[1])?;
         match parts[0] {
-            "nop" => Nop(arg),
-            "acc" => Acc(arg),
-            "jmp" => Jmp(arg),
-            _ => panic!("Did not recognize `{}`", parts[0])
+            "nop" => Ok(Nop(arg)),
+            "acc" => Ok(Acc(arg)),
+            "jmp" => Ok(Jmp(arg)),
+            _ => Err(format!("Did not recognize `{}`", parts[0]))
         }
     }
 
@@ -63,12 +77,12 @@ pub struct CPUState {
 }
 
 impl CPUState {
-    pub fn from_file(filename: &str) -> Self {
-        CPUState {program: all_lines(filename).unwrap()
-            .map(|line| Instruction::from(line.as_str()))
-            .collect(),
+    pub fn from_file(filename: &str) -> AdventResult<Self> {
+        Ok(CPUState {program: numbered_lines(filename)?
+            .map(|(line_num, line)| Instruction::from(line.as_str()).at_line(DAY, line_num, line.as_str()))
+            .collect::<AdventResult<_>>()?,
             pc: 0,
-            accumulator: 0}
+            accumulator: 0})
     }
 
     pub fn advance(&mut self) {
@@ -96,14 +110,37 @@ impl CPUState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::input::InputFile;
 
     #[test]
     pub fn test_solve_1() {
-        assert_eq!(solve_1("in/day8_ex.txt").unwrap(), "5");
+        assert_eq!(solve_1("fixtures/day8_ex.txt").unwrap(), "5");
+    }
+
+    #[test]
+    pub fn test_instruction() {
+        assert_eq!(Instruction::from("acc -99").unwrap(), Acc(-99));
+        assert_eq!(Instruction::from("jmp +4").unwrap(), Jmp(4));
+        assert_eq!(Instruction::from("hlt +0").unwrap_err(), "Did not recognize `hlt`");
+        assert_eq!(Instruction::from("nop").unwrap_err(), "expected `<op> <arg>`");
+        assert_eq!(Instruction::from("nop +x").unwrap_err(), "`+x` is not a number");
+    }
+
+    #[test]
+    pub fn test_from_file_error() {
+        let input = InputFile::from_reader("nop +0\nacc +1\nhlt +0\n".as_bytes()).unwrap();
+        assert_eq!(solve_1(input.filename()).unwrap_err().to_string(),
+                   "day 8, line 3: Did not recognize `hlt` in `hlt +0`");
     }
 
     #[test]
     pub fn test_solve_2() {
-        assert_eq!(solve_2("in/day8_ex.txt").unwrap(), "8");
+        assert_eq!(solve_2("fixtures/day8_ex.txt").unwrap(), "8");
+    }
+
+    #[test]
+    pub fn test_unfixable() {
+        let input = InputFile::from_reader("jmp +0\njmp -1\n".as_bytes()).unwrap();
+        assert_eq!(solve_2(input.filename()).unwrap_err().to_string(), "day 8: no solution: program cannot be fixed");
     }
 }
\ No newline at end of file
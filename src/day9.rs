@@ -1,16 +1,28 @@
-use advent_code_lib::file2nums;
-use std::io;
 use std::collections::VecDeque;
+use crate::puzzle::PuzzleRegistry;
+use crate::input::numbers;
+use crate::error::{AdventResult, AdventError};
 
-pub fn solve_1() -> io::Result<String> {
-    Ok(find_failing_xmas_num(&file2nums("in/day9.txt")?, 25).unwrap().to_string())
+const DAY: usize = 9;
+
+pub fn solve_1(filename: &str) -> AdventResult<String> {
+    find_failing_xmas_num(&numbers(filename, DAY)?, 25)
+        .map(|n| n.to_string())
+        .ok_or_else(|| AdventError::no_solution(DAY, "every number is a sum of two in its preamble"))
+}
+
+pub fn solve_2(filename: &str) -> AdventResult<String> {
+    find_encryption_weakness(&numbers(filename, DAY)?, 25)
+        .map(|n| n.to_string())
+        .ok_or_else(|| AdventError::no_solution(DAY, "no contiguous range sums to the failing number"))
 }
 
-pub fn solve_2() -> io::Result<String> {
-    Ok(find_encryption_weakness(&file2nums("in/day9.txt")?, 25).unwrap().to_string())
+pub fn register(registry: &mut PuzzleRegistry) {
+    registry.add(9, 1, solve_1);
+    registry.add(9, 2, solve_2);
 }
 
-fn find_encryption_weakness(nums: &Vec<isize>, preamble_length: usize) -> Option<isize> {
+pub fn find_encryption_weakness(nums: &[isize], preamble_length: usize) -> Option<isize> {
     match find_failing_xmas_num(nums, preamble_length) {
         None => None,
         Some(failing) => {
@@ -25,7 +37,7 @@ fn find_encryption_weakness(nums: &Vec<isize>, preamble_length: usize) -> Option
     }
 }
 
-fn find_failing_xmas_num(nums: &Vec<isize>, preamble_length: usize) -> Option<isize> {
+pub fn find_failing_xmas_num(nums: &[isize], preamble_length: usize) -> Option<isize> {
     let mut prev_preamble = VecDeque::new();
     for num in nums.iter() {
         if prev_preamble.len() == preamble_length {
@@ -50,7 +62,7 @@ fn find_pair_sum(prev: &VecDeque<isize>, target: isize) -> Option<(isize,isize)>
     None
 }
 
-fn find_contiguous_sequence_pair(nums: &Vec<isize>, start: usize, target: isize) -> Option<(isize,isize)> {
+fn find_contiguous_sequence_pair(nums: &[isize], start: usize, target: isize) -> Option<(isize,isize)> {
     let mut sum = 0;
     let mut min = nums[start];
     let mut max = nums[start];
@@ -73,11 +85,11 @@ mod tests {
 
     #[test]
     fn test_puzzle_1() {
-        assert_eq!(find_failing_xmas_num(&file2nums("in/day9_ex.txt").unwrap(), 5).unwrap(), 127);
+        assert_eq!(find_failing_xmas_num(&numbers("fixtures/day9_ex.txt", DAY).unwrap(), 5).unwrap(), 127);
     }
 
     #[test]
     fn test_puzzle_2() {
-        assert_eq!(find_encryption_weakness(&file2nums("in/day9_ex.txt").unwrap(), 5).unwrap(), 62);
+        assert_eq!(find_encryption_weakness(&numbers("fixtures/day9_ex.txt", DAY).unwrap(), 5).unwrap(), 62);
     }
 }
\ No newline at end of file
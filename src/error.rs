@@ -0,0 +1,88 @@
+use std::{fmt, io};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+#[derive(Debug)]
+pub enum AdventError {
+    Io(io::Error),
+    Parse {day: usize, line: usize, text: String, reason: String},
+    BadInput {day: usize, reason: String},
+    NoSolution {day: usize, reason: String},
+    Panicked(String)
+}
+
+pub type AdventResult<T> = Result<T, AdventError>;
+
+impl AdventError {
+    pub fn parse(day: usize, line: usize, text: &str, reason: &str) -> Self {
+        AdventError::Parse {day, line, text: text.to_string(), reason: reason.to_string()}
+    }
+
+    // Input that is malformed as a whole rather than on any one line, e.g. empty or missing a section.
+    pub fn bad_input(day: usize, reason: &str) -> Self {
+        AdventError::BadInput {day, reason: reason.to_string()}
+    }
+
+    pub fn no_solution(day: usize, reason: &str) -> Self {
+        AdventError::NoSolution {day, reason: reason.to_string()}
+    }
+}
+
+impl Display for AdventError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            AdventError::Io(e) => write!(f, "{}", e),
+            AdventError::Parse {day, line, text, reason} =>
+                write!(f, "day {}, line {}: {} in `{}`", day, line, reason, text),
+            AdventError::BadInput {day, reason} => write!(f, "day {}: bad input: {}", day, reason),
+            AdventError::NoSolution {day, reason} => write!(f, "day {}: no solution: {}", day, reason),
+            AdventError::Panicked(message) => write!(f, "panicked: {}", message)
+        }
+    }
+}
+
+impl std::error::Error for AdventError {}
+
+impl From<io::Error> for AdventError {
+    fn from(e: io::Error) -> Self {
+        AdventError::Io(e)
+    }
+}
+
+// Parsers below the line level report only a reason; the caller that knows
+// which line it is reading attaches the day, line number and line text.
+pub trait LineContext<T> {
+    fn at_line(self, day: usize, line: usize, text: &str) -> AdventResult<T>;
+}
+
+impl <T> LineContext<T> for Result<T,String> {
+    fn at_line(self, day: usize, line: usize, text: &str) -> AdventResult<T> {
+        self.map_err(|reason| AdventError::parse(day, line, text, reason.as_str()))
+    }
+}
+
+pub fn parse_num<N: FromStr>(text: &str) -> Result<N, String> {
+    text.parse::<N>().map_err(|_| format!("`{}` is not a number", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        assert_eq!(AdventError::parse(8, 3, "nope +1", "unrecognized instruction `nope`").to_string(),
+                   "day 8, line 3: unrecognized instruction `nope` in `nope +1`");
+        assert_eq!(AdventError::no_solution(5, "no single-seat gap").to_string(),
+                   "day 5: no solution: no single-seat gap");
+        assert_eq!(AdventError::bad_input(13, "missing bus line").to_string(), "day 13: bad input: missing bus line");
+        assert_eq!(AdventError::from(io::Error::new(io::ErrorKind::NotFound, "missing")).to_string(), "missing");
+    }
+
+    #[test]
+    fn test_at_line() {
+        assert_eq!(parse_num::<usize>("127").at_line(9, 2, "127").unwrap(), 127);
+        assert_eq!(parse_num::<usize>("12x").at_line(9, 2, "12x").unwrap_err().to_string(),
+                   "day 9, line 2: `12x` is not a number in `12x`");
+    }
+}
@@ -0,0 +1,155 @@
+use std::{io, fs, process};
+use std::io::{Read, Write};
+use std::fs::OpenOptions;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use advent_code_lib::all_lines;
+use crate::puzzle::default_input;
+use crate::error::{AdventResult, LineContext, parse_num};
+
+pub const STDIN_ARG: &str = "-";
+
+// Solvers read their input by filename, and some read it more than once,
+// so stdin is copied into a temporary file that is removed when dropped.
+#[derive(Debug)]
+pub struct InputFile {
+    filename: String,
+    temporary: bool
+}
+
+impl InputFile {
+    pub fn from_arg(arg: &str) -> io::Result<Self> {
+        if arg == STDIN_ARG {
+            InputFile::from_reader(io::stdin())
+        } else {
+            Ok(InputFile {filename: arg.to_string(), temporary: false})
+        }
+    }
+
+    pub fn default_for(day: usize) -> Self {
+        InputFile {filename: default_input(day), temporary: false}
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut contents = String::new();
+        reader.read_to_string(&mut contents)?;
+        let path = write_new(contents.as_str(), temp_path)?;
+        Ok(InputFile {filename: path.to_string_lossy().to_string(), temporary: true})
+    }
+
+    pub fn filename(&self) -> &str {
+        self.filename.as_str()
+    }
+}
+
+impl Drop for InputFile {
+    fn drop(&mut self) {
+        if self.temporary {
+            let _ = fs::remove_file(&self.filename);
+        }
+    }
+}
+
+pub fn numbered_lines(filename: &str) -> io::Result<impl Iterator<Item=(usize,String)>> {
+    Ok(all_lines(filename)?.enumerate().map(|(i, line)| (i + 1, line)))
+}
+
+// One number per line.
+pub fn numbers(filename: &str, day: usize) -> AdventResult<Vec<isize>> {
+    numbered_lines(filename)?
+        .map(|(line_num, line)| parse_num(line.trim()).at_line(day, line_num, line.as_str()))
+        .collect()
+}
+
+// Puzzle inputs are personal to each solver and live untracked under `in/`;
+// tests and benchmarks that need one skip themselves when it is absent.
+pub fn personal_input(day: usize) -> Option<String> {
+    let filename = default_input(day);
+    if Path::new(filename.as_str()).exists() {
+        Some(filename)
+    } else {
+        eprintln!("skipping: {} not found", filename);
+        None
+    }
+}
+
+static TEMP_COUNT: AtomicUsize = AtomicUsize::new(0);
+
+// Writes to the first path from `next_path` that does not exist yet. Creating the file
+// exclusively means a file or symlink planted at a predictable name is never written through.
+fn write_new<F: FnMut() -> PathBuf>(contents: &str, mut next_path: F) -> io::Result<PathBuf> {
+    loop {
+        let path = next_path();
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(mut file) => {
+                file.write_all(contents.as_bytes())?;
+                return Ok(path);
+            }
+            Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e)
+        }
+    }
+}
+
+fn temp_path() -> PathBuf {
+    let mut path = std::env::temp_dir();
+    let count = TEMP_COUNT.fetch_add(1, Ordering::SeqCst);
+    path.push(format!("code_advent_2020_{}_{}.txt", process::id(), count));
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_arg() {
+        let input = InputFile::from_arg("in/day3.txt").unwrap();
+        assert_eq!(input.filename(), "in/day3.txt");
+        assert_eq!(InputFile::default_for(3).filename(), "in/day3.txt");
+    }
+
+    #[test]
+    fn test_from_reader() {
+        let filename = {
+            let input = InputFile::from_reader("1721\n979\n366\n".as_bytes()).unwrap();
+            for _ in 0..2 {
+                assert_eq!(all_lines(input.filename()).unwrap().collect::<Vec<_>>(), vec!["1721", "979", "366"]);
+            }
+            input.filename().to_string()
+        };
+        assert!(fs::metadata(filename).is_err());
+    }
+
+    #[test]
+    fn test_write_new_skips_existing() {
+        let taken = InputFile::from_reader("keep\n".as_bytes()).unwrap();
+        let fresh = temp_path();
+        let mut paths = vec![fresh.clone(), PathBuf::from(taken.filename())];
+        let written = write_new("new\n", || paths.pop().unwrap()).unwrap();
+        assert_eq!(written, fresh);
+        assert_eq!(fs::read_to_string(taken.filename()).unwrap(), "keep\n");
+        assert_eq!(fs::read_to_string(&written).unwrap(), "new\n");
+        fs::remove_file(written).unwrap();
+    }
+
+    #[test]
+    fn test_personal_input() {
+        assert_eq!(personal_input(30), None);
+    }
+
+    #[test]
+    fn test_numbered_lines() {
+        let input = InputFile::from_reader("a\nb\n".as_bytes()).unwrap();
+        assert_eq!(numbered_lines(input.filename()).unwrap().collect::<Vec<_>>(),
+                   vec![(1, "a".to_string()), (2, "b".to_string())]);
+    }
+
+    #[test]
+    fn test_numbers() {
+        let input = InputFile::from_reader("12\n-3\n".as_bytes()).unwrap();
+        assert_eq!(numbers(input.filename(), 9).unwrap(), vec![12, -3]);
+        let input = InputFile::from_reader("12\nx\n".as_bytes()).unwrap();
+        assert_eq!(numbers(input.filename(), 9).unwrap_err().to_string(), "day 9, line 2: `x` is not a number in `x`");
+    }
+}
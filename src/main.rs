@@ -1,93 +1,120 @@
-#![feature(map_first_last)]
-#[macro_use] extern crate maplit;
+use std::{env,process};
+use code_advent_2020::{day2, day4, day6, day7, runner, verify};
+use code_advent_2020::puzzle::PuzzleRegistry;
+use code_advent_2020::input::InputFile;
+use code_advent_2020::error::AdventResult;
 
-pub mod day1;
-pub mod day2;
-pub mod day3;
-pub mod day4;
-pub mod day5;
-pub mod day6;
-pub mod day7;
-pub mod day8;
-pub mod day9;
-pub mod day10;
-pub mod day11;
-pub mod day12;
-pub mod day13;
-pub mod day14;
-pub mod day15;
-pub mod day16;
-pub mod day17;
-pub mod day18;
-pub mod day19;
-pub mod day20;
-pub mod day21;
-pub mod day22;
-pub mod day23;
-pub mod day24;
-pub mod day25;
+const JSON_FLAG: &str = "--json";
+const VERBOSE_FLAG: &str = "--verbose";
+const STRICT_FLAG: &str = "--strict";
 
-use std::{env,io};
-use std::time::Instant;
+fn main() {
+    if let Err(e) = run() {
+        eprintln!("error: {}", e);
+        process::exit(1);
+    }
+}
 
-fn main() -> io::Result<()> {
-    let args: Vec<String> = env::args().collect();
-    let now = Instant::now();
-    println!("{}", match args.get(1) {
-        None => "Usage: code_advent_2020 puzzle_num [other_args]*".to_string(),
-        Some(arg) => match arg.as_str() {
-            "1_1" => day1::solve_1()?,
-            "1_2" => day1::solve_2()?,
-            "2_1" => day2::solve_1()?,
-            "2_2" => day2::solve_2()?,
-            "3_1" => day3::solve_1("in/day3.txt")?,
-            "3_2" => day3::solve_2("in/day3.txt")?,
-            "4_1" => day4::solve_1("in/day4.txt")?,
-            "4_2" => day4::solve_2("in/day4.txt")?,
-            "5_1" => day5::solve_1()?,
-            "5_2" => day5::solve_2()?,
-            "6_1" => day6::solve_1("in/day6.txt")?,
-            "6_2" => day6::solve_2("in/day6.txt")?,
-            "7_1" => day7::solve_1("in/day7.txt")?,
-            "7_2" => day7::solve_2("in/day7.txt")?,
-            "8_1" => day8::solve_1("in/day8.txt")?,
-            "8_2" => day8::solve_2("in/day8.txt")?,
-            "9_1" => day9::solve_1()?,
-            "9_2" => day9::solve_2()?,
-            "10_1" => day10::solve_1("in/day10.txt")?,
-            "10_2" => day10::solve_2("in/day10.txt")?,
-            "11_1" => day11::solve_1("in/day11.txt")?,
-            "11_2" => day11::solve_2("in/day11.txt")?,
-            "12_1" => day12::solve_1("in/day12.txt")?,
-            "12_2" => day12::solve_2("in/day12.txt")?,
-            "13_1" => day13::solve_1("in/day13.txt")?,
-            "13_2" => day13::solve_2()?,
-            "14_1" => day14::solve_1("in/day14.txt")?,
-            "14_2" => day14::solve_2("in/day14.txt")?,
-            "15_1" => day15::solve_1(),
-            "15_2" => day15::solve_2(),
-            "16_1" => day16::solve_1()?,
-            "16_2" => day16::solve_2("in/day16.txt")?,
-            "17_1" => day17::solve_1("in/day17.txt")?,
-            "17_2" => day17::solve_2("in/day17.txt")?,
-            "18_1" => day18::solve_1("in/day18.txt")?,
-            "18_2" => day18::solve_2("in/day18.txt")?,
-            "19_1" => day19::solve_1("in/day19.txt")?,
-            "19_2" => day19::solve_2("in/day19.txt")?,
-            "20_1" => day20::solve_1("in/day20.txt")?,
-            "20_2" => day20::solve_2("in/day20.txt")?,
-            "21_1" => day21::solve_1("in/day21.txt")?,
-            "21_2" => day21::solve_2("in/day21.txt")?,
-            "22_1" => day22::solve_1("in/day22.txt")?,
-            "22_2" => day22::solve_2("in/day22.txt")?,
-            "23_1" => day23::solve_1([1,5,6,7,9,4,8,2,3]),
-            "23_2" => day23::solve_2([1,5,6,7,9,4,8,2,3]),
-            "24_1" => day24::solve_1("in/day24.txt")?,
-            "24_2" => day24::solve_2("in/day24.txt")?,
-            "25_1" => day25::solve_1(),
-            _ => "Unrecognized problem".to_string()
+fn run() -> AdventResult<()> {
+    let mut args: Vec<String> = env::args().collect();
+    let json = take_flag(&mut args, JSON_FLAG);
+    let verbose = take_flag(&mut args, VERBOSE_FLAG);
+    let mode = if take_flag(&mut args, STRICT_FLAG) {day4::Mode::Strict} else {day4::Mode::Lenient};
+    let registry = PuzzleRegistry::all();
+    match args.get(1).map(|s| s.as_str()) {
+        Some("all") => report(&runner::run_all(registry.iter(), None), json),
+        Some("day") => match args.get(2).and_then(|d| d.parse::<usize>().ok()) {
+            Some(day) if registry.day(day).count() > 0 => {
+                let input = input_arg(&args, 3, day)?;
+                report(&runner::run_all(registry.day(day), Some(input.filename())), json);
+            }
+            _ => fail("Usage: code_advent_2020 day day_num (1-25) [input_file | -] [--json]")
+        },
+        Some("verify") => {
+            let answers = verify::Answers::from_file(args.get(2).map_or(verify::DEFAULT_ANSWERS, |a| a.as_str()))?;
+            let verified = verify::verify_all(&registry, &answers);
+            let unknown = verify::unknown_keys(&registry, &answers);
+            print!("{}", verify::verify_report(&verified, &unknown, &answers));
+            if !verify::all_passed(&verified, &unknown) {
+                process::exit(1);
+            }
+        }
+        Some("policy") => match args.get(2).map(|names| day2::policies_named(names)) {
+            Some(Ok(policies)) => {
+                let input = input_arg(&args, 3, 2)?;
+                print!("{}", day2::policy_report(input.filename(), &policies)?);
+            }
+            Some(Err(e)) => fail(e.as_str()),
+            None => {
+                let names: Vec<&str> = day2::all_policies().iter().map(|p| p.name()).collect();
+                fail(format!("Usage: code_advent_2020 policy (all | name,...) [input_file | -]; names: {}", names.join(", ")).as_str());
+            }
+        },
+        Some("passports") => match args.get(2) {
+            Some(schema) => {
+                let schema = if schema == "default" {day4::Schema::default()} else {day4::Schema::from_file(schema)?};
+                let input = input_arg(&args, 3, 4)?;
+                if verbose {
+                    print!("{}", day4::validation_report(input.filename(), &schema, mode)?);
+                } else {
+                    let (valid, total) = day4::count_valid(input.filename(), &schema, mode)?;
+                    println!("{} of {} passports valid", valid, total);
+                }
+            }
+            None => fail("Usage: code_advent_2020 passports (schema_file | default) [input_file | -] [--verbose] [--strict]")
+        },
+        Some("customs") => print!("{}", day6::answer_report(input_arg(&args, 2, 6)?.filename())?),
+        Some("dot") => match args.get(2).map(|spec| day7::Subgraph::parse(spec)) {
+            Some(Ok(subgraph)) => {
+                let graph = day7::create_graph_from(input_arg(&args, 3, 7)?.filename())?;
+                print!("{}", graph.to_dot(subgraph));
+            }
+            Some(Err(e)) => fail(e.as_str()),
+            None => fail("Usage: code_advent_2020 dot (all | from:color | to:color) [input_file | -]")
+        },
+        None => fail("Usage: code_advent_2020 [puzzle_num | all | day day_num | verify | policy names | passports schema | customs | dot subgraph] [input_file | answers_file | -] [--json]"),
+        Some(arg) => match registry.from_key(arg) {
+            Some(puzzle) => {
+                let result = runner::run(puzzle, input_arg(&args, 2, puzzle.day())?.filename());
+                match &result.answer {
+                    _ if json => println!("{}", runner::json_line(&result)),
+                    Ok(answer) => println!("{}\n{} s elapsed", answer, result.elapsed.as_secs_f32()),
+                    Err(e) => eprintln!("error: {}", e)
+                }
+                if result.answer.is_err() {
+                    process::exit(1);
+                }
+            }
+            None => fail("Unrecognized problem")
         }
-    });
-    println!("{} s elapsed", now.elapsed().as_secs_f32());
+    }
     Ok(())
 }
+
+fn fail(message: &str) -> ! {
+    eprintln!("{}", message);
+    process::exit(1);
+}
+
+fn take_flag(args: &mut Vec<String>, flag: &str) -> bool {
+    let before = args.len();
+    args.retain(|arg| arg != flag);
+    args.len() < before
+}
+
+fn report(results: &[runner::RunResult], json: bool) {
+    if json {
+        for result in results.iter() {
+            println!("{}", runner::json_line(result));
+        }
+    } else {
+        print!("{}", runner::timing_table(results));
+    }
+}
+
+fn input_arg(args: &[String], i: usize, day: usize) -> AdventResult<InputFile> {
+    match args.get(i) {
+        Some(arg) => Ok(InputFile::from_arg(arg.as_str())?),
+        None => Ok(InputFile::default_for(day))
+    }
+}
@@ -0,0 +1,148 @@
+use std::collections::BTreeMap;
+use crate::error::AdventResult;
+
+pub trait Puzzle {
+    fn day(&self) -> usize;
+    fn part(&self) -> usize;
+    fn solve(&self, filename: &str) -> AdventResult<String>;
+
+    fn key(&self) -> String {
+        format!("{}_{}", self.day(), self.part())
+    }
+}
+
+#[derive(Copy,Clone)]
+pub struct Solution {
+    day: usize,
+    part: usize,
+    solver: fn(&str) -> AdventResult<String>
+}
+
+impl Puzzle for Solution {
+    fn day(&self) -> usize {self.day}
+
+    fn part(&self) -> usize {self.part}
+
+    fn solve(&self, filename: &str) -> AdventResult<String> {
+        (self.solver)(filename)
+    }
+}
+
+pub fn default_input(day: usize) -> String {
+    format!("in/day{}.txt", day)
+}
+
+pub fn parse_key(key: &str) -> Option<(usize,usize)> {
+    let mut parts = key.split('_');
+    let day = parts.next()?.parse::<usize>().ok()?;
+    let part = parts.next()?.parse::<usize>().ok()?;
+    if parts.next().is_some() {None} else {Some((day, part))}
+}
+
+#[derive(Default)]
+pub struct PuzzleRegistry {
+    puzzles: BTreeMap<(usize,usize),Box<dyn Puzzle>>
+}
+
+impl PuzzleRegistry {
+    pub fn new() -> Self {PuzzleRegistry {puzzles: BTreeMap::new()}}
+
+    pub fn all() -> Self {
+        let mut registry = PuzzleRegistry::new();
+        for register in [crate::day1::register, crate::day2::register, crate::day3::register,
+            crate::day4::register, crate::day5::register, crate::day6::register,
+            crate::day7::register, crate::day8::register, crate::day9::register,
+            crate::day10::register, crate::day11::register, crate::day12::register,
+            crate::day13::register, crate::day14::register, crate::day15::register,
+            crate::day16::register, crate::day17::register, crate::day18::register,
+            crate::day19::register, crate::day20::register, crate::day21::register,
+            crate::day22::register, crate::day23::register, crate::day24::register,
+            crate::day25::register].iter() {
+            register(&mut registry);
+        }
+        registry
+    }
+
+    pub fn add(&mut self, day: usize, part: usize, solver: fn(&str) -> AdventResult<String>) {
+        self.register(Box::new(Solution {day, part, solver}));
+    }
+
+    pub fn register(&mut self, puzzle: Box<dyn Puzzle>) {
+        self.puzzles.insert((puzzle.day(), puzzle.part()), puzzle);
+    }
+
+    pub fn get(&self, day: usize, part: usize) -> Option<&dyn Puzzle> {
+        self.puzzles.get(&(day, part)).map(|p| p.as_ref())
+    }
+
+    pub fn from_key(&self, key: &str) -> Option<&dyn Puzzle> {
+        let (day, part) = parse_key(key)?;
+        self.get(day, part)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&dyn Puzzle> {
+        self.puzzles.values().map(|p| p.as_ref())
+    }
+
+    pub fn day(&self, day: usize) -> impl Iterator<Item=&dyn Puzzle> {
+        self.puzzles.range((day, 0)..=(day, usize::MAX)).map(|(_, p)| p.as_ref())
+    }
+
+    pub fn len(&self) -> usize {self.puzzles.len()}
+
+    pub fn is_empty(&self) -> bool {self.puzzles.is_empty()}
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_all_registered() {
+        let registry = PuzzleRegistry::all();
+        assert_eq!(registry.len(), 49);
+        assert!(PuzzleRegistry::default().is_empty());
+        for day in 1..=25 {
+            assert!(registry.get(day, 1).is_some());
+            assert_eq!(registry.get(day, 2).is_some(), day != 25);
+        }
+    }
+
+    #[test]
+    fn test_from_key() {
+        let registry = PuzzleRegistry::all();
+        let puzzle = registry.from_key("7_2").unwrap();
+        assert_eq!((puzzle.day(), puzzle.part()), (7, 2));
+        assert_eq!(puzzle.key(), "7_2");
+        for bad in ["25_2", "7", "7_2_1", "x_1", ""].iter() {
+            assert!(registry.from_key(bad).is_none());
+        }
+    }
+
+    #[test]
+    fn test_iter_in_order() {
+        let keys: Vec<(usize,usize)> = PuzzleRegistry::all().iter()
+            .map(|p| (p.day(), p.part()))
+            .collect();
+        let mut sorted = keys.clone();
+        sorted.sort();
+        assert_eq!(keys, sorted);
+    }
+
+    #[test]
+    fn test_day() {
+        let registry = PuzzleRegistry::all();
+        let parts: Vec<usize> = registry.day(7).map(|p| p.part()).collect();
+        assert_eq!(parts, vec![1, 2]);
+        assert_eq!(registry.day(25).count(), 1);
+        assert_eq!(registry.day(26).count(), 0);
+    }
+
+    #[test]
+    fn test_solution() {
+        let mut registry = PuzzleRegistry::new();
+        registry.add(30, 1, |filename| Ok(filename.to_uppercase()));
+        assert_eq!(registry.get(30, 1).unwrap().solve("abc").unwrap(), "ABC");
+        assert_eq!(default_input(30), "in/day30.txt");
+    }
+}
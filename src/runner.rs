@@ -0,0 +1,166 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use crate::puzzle::{Puzzle, default_input};
+use crate::error::{AdventResult, AdventError};
+
+pub struct RunResult {
+    pub day: usize,
+    pub part: usize,
+    pub answer: AdventResult<String>,
+    pub elapsed: Duration
+}
+
+impl RunResult {
+    pub fn answer_str(&self) -> String {
+        match &self.answer {
+            Ok(answer) => answer.clone(),
+            Err(e) => format!("error: {}", e)
+        }
+    }
+}
+
+// The panic hook is process-wide, so runs that swap it out take turns.
+static QUIET_PANICS: Mutex<()> = Mutex::new(());
+
+pub fn run(puzzle: &dyn Puzzle, filename: &str) -> RunResult {
+    let now = Instant::now();
+    let answer = {
+        let _turn = QUIET_PANICS.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // A caught panic becomes the result's error, so the hook need not print it too.
+        let hook = panic::take_hook();
+        panic::set_hook(Box::new(|_| {}));
+        let answer = panic::catch_unwind(AssertUnwindSafe(|| puzzle.solve(filename)));
+        panic::set_hook(hook);
+        answer.unwrap_or_else(|cause| Err(AdventError::Panicked(panic_message(cause))))
+    };
+    RunResult {day: puzzle.day(), part: puzzle.part(), answer, elapsed: now.elapsed()}
+}
+
+fn panic_message(cause: Box<dyn std::any::Any + Send>) -> String {
+    if let Some(s) = cause.downcast_ref::<&str>() {
+        s.to_string()
+    } else if let Some(s) = cause.downcast_ref::<String>() {
+        s.clone()
+    } else {
+        "unknown cause".to_string()
+    }
+}
+
+pub fn run_all<'a, I: Iterator<Item=&'a dyn Puzzle>>(puzzles: I, filename: Option<&str>) -> Vec<RunResult> {
+    puzzles.map(|puzzle| match filename {
+        Some(filename) => run(puzzle, filename),
+        None => run(puzzle, default_input(puzzle.day()).as_str())
+    }).collect()
+}
+
+pub fn timing_table(results: &[RunResult]) -> String {
+    let answers: Vec<String> = results.iter().map(|r| r.answer_str()).collect();
+    let width = answers.iter().map(|a| a.len()).max().unwrap_or(0).max("Answer".len());
+    let mut table = format!("{:>3} {:>4}  {:<width$}  {:>10}\n", "Day", "Part", "Answer", "Time (s)", width = width);
+    for (result, answer) in results.iter().zip(answers.iter()) {
+        table.push_str(format!("{:>3} {:>4}  {:<width$}  {:>10.6}\n",
+                               result.day, result.part, answer, result.elapsed.as_secs_f32(), width = width).as_str());
+    }
+    let total: Duration = results.iter().map(|r| r.elapsed).sum();
+    table.push_str(format!("{:<width$}  {:>10.6}\n", "Total", total.as_secs_f32(), width = width + 10).as_str());
+    table
+}
+
+pub fn status(result: &RunResult) -> &'static str {
+    match &result.answer {
+        Ok(_) => "ok",
+        Err(AdventError::Panicked(_)) => "panicked",
+        Err(_) => "error"
+    }
+}
+
+pub fn json_line(result: &RunResult) -> String {
+    let answer = match &result.answer {
+        Ok(answer) => json_string(answer.as_str()),
+        Err(_) => "null".to_string()
+    };
+    let error = match &result.answer {
+        Ok(_) => String::new(),
+        Err(e) => format!(",\"error\":{}", json_string(e.to_string().as_str()))
+    };
+    format!("{{\"day\":{},\"part\":{},\"answer\":{},\"elapsed_ns\":{},\"status\":\"{}\"{}}}",
+            result.day, result.part, answer, result.elapsed.as_nanos(), status(result), error)
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(format!("\\u{:04x}", c as u32).as_str()),
+            c => escaped.push(c)
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io;
+    use crate::puzzle::PuzzleRegistry;
+
+    #[test]
+    fn test_run() {
+        let mut registry = PuzzleRegistry::new();
+        registry.add(30, 1, |filename| Ok(filename.to_string()));
+        let result = run(registry.get(30, 1).unwrap(), "in/day30.txt");
+        assert_eq!((result.day, result.part), (30, 1));
+        assert_eq!(result.answer_str(), "in/day30.txt");
+    }
+
+    #[test]
+    fn test_run_panic() {
+        let mut registry = PuzzleRegistry::new();
+        registry.add(30, 1, |_| panic!("Illegal value: {}", 'q'));
+        let result = run(registry.get(30, 1).unwrap(), "in/day30.txt");
+        assert_eq!(result.answer_str(), "error: panicked: Illegal value: q");
+    }
+
+    #[test]
+    fn test_run_all() {
+        let mut registry = PuzzleRegistry::new();
+        registry.add(30, 1, |filename| Ok(filename.to_string()));
+        registry.add(30, 2, |filename| Ok(filename.len().to_string()));
+        let answers: Vec<String> = run_all(registry.iter(), None).iter().map(|r| r.answer_str()).collect();
+        assert_eq!(answers, vec!["in/day30.txt", "12"]);
+        let answers: Vec<String> = run_all(registry.day(30), Some("mine.txt")).iter().map(|r| r.answer_str()).collect();
+        assert_eq!(answers, vec!["mine.txt", "8"]);
+    }
+
+    #[test]
+    fn test_timing_table() {
+        let results = vec![
+            RunResult {day: 1, part: 1, answer: Ok("802011".to_string()), elapsed: Duration::from_millis(250)},
+            RunResult {day: 1, part: 2, answer: Err(AdventError::from(io::Error::new(io::ErrorKind::NotFound, "missing"))), elapsed: Duration::from_millis(500)}
+        ];
+        assert_eq!(timing_table(&results), "\
+Day Part  Answer            Time (s)
+  1    1  802011            0.250000
+  1    2  error: missing    0.500000
+Total                       0.750000
+");
+    }
+
+    #[test]
+    fn test_json_line() {
+        let ok = RunResult {day: 1, part: 1, answer: Ok("1477 + 543 == 2020".to_string()), elapsed: Duration::from_nanos(1500)};
+        assert_eq!(json_line(&ok), r#"{"day":1,"part":1,"answer":"1477 + 543 == 2020","elapsed_ns":1500,"status":"ok"}"#);
+        let err = RunResult {day: 8, part: 1, answer: Err(AdventError::parse(8, 3, "nope \"+1\"", "unrecognized")), elapsed: Duration::from_nanos(7)};
+        assert_eq!(json_line(&err), r#"{"day":8,"part":1,"answer":null,"elapsed_ns":7,"status":"error","error":"day 8, line 3: unrecognized in `nope \"+1\"`"}"#);
+        let panicked = RunResult {day: 5, part: 2, answer: Err(AdventError::Panicked("bad\tseat\n".to_string())), elapsed: Duration::from_nanos(0)};
+        assert_eq!(json_line(&panicked), r#"{"day":5,"part":2,"answer":null,"elapsed_ns":0,"status":"panicked","error":"panicked: bad\tseat\n"}"#);
+        assert_eq!(json_string("a\u{1}b\\"), r#""a\u0001b\\""#);
+    }
+}
@@ -0,0 +1,167 @@
+use std::io;
+use std::collections::BTreeMap;
+use advent_code_lib::all_lines;
+use crate::puzzle::{PuzzleRegistry, parse_key};
+use crate::runner::{RunResult, run_all};
+
+pub const DEFAULT_ANSWERS: &str = "answers.txt";
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub struct Answers {
+    expected: BTreeMap<(usize,usize),String>
+}
+
+impl Answers {
+    pub fn from_file(filename: &str) -> io::Result<Self> {
+        let mut expected = BTreeMap::new();
+        for line in all_lines(filename)? {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let mut parts = line.splitn(2, char::is_whitespace);
+            let key = parts.next().unwrap();
+            let answer = parts.next().map_or("", |a| a.trim());
+            match parse_key(key) {
+                Some(day_part) if !answer.is_empty() => {expected.insert(day_part, answer.to_string());}
+                _ => return Err(io::Error::new(io::ErrorKind::InvalidData, format!("Malformed answer line: `{}`", line)))
+            }
+        }
+        Ok(Answers {expected})
+    }
+
+    pub fn get(&self, day: usize, part: usize) -> Option<&str> {
+        self.expected.get(&(day, part)).map(|s| s.as_str())
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item=(usize,usize)> + '_ {
+        self.expected.keys().copied()
+    }
+
+    pub fn len(&self) -> usize {self.expected.len()}
+
+    pub fn is_empty(&self) -> bool {self.expected.is_empty()}
+}
+
+#[derive(Debug,Clone,Eq,PartialEq)]
+pub enum Verdict {
+    Pass, Fail(String), Missing
+}
+
+pub fn verdict(result: &RunResult, answers: &Answers) -> Verdict {
+    match answers.get(result.day, result.part) {
+        None => Verdict::Missing,
+        Some(expected) => {
+            let actual = result.answer_str();
+            if actual == expected {Verdict::Pass} else {Verdict::Fail(actual)}
+        }
+    }
+}
+
+pub fn verify_all(registry: &PuzzleRegistry, answers: &Answers) -> Vec<(RunResult, Verdict)> {
+    run_all(registry.iter(), None).into_iter()
+        .map(|result| {
+            let verdict = verdict(&result, answers);
+            (result, verdict)
+        })
+        .collect()
+}
+
+// Answer keys naming a day and part that no registered puzzle solves.
+pub fn unknown_keys(registry: &PuzzleRegistry, answers: &Answers) -> Vec<(usize,usize)> {
+    answers.keys().filter(|(day, part)| registry.get(*day, *part).is_none()).collect()
+}
+
+pub fn all_passed(verified: &[(RunResult, Verdict)], unknown: &[(usize,usize)]) -> bool {
+    unknown.is_empty() && verified.iter().all(|(_, v)| !matches!(v, Verdict::Fail(_)))
+}
+
+pub fn verify_report(verified: &[(RunResult, Verdict)], unknown: &[(usize,usize)], answers: &Answers) -> String {
+    let mut report = String::new();
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    for (result, verdict) in verified.iter() {
+        let line = match verdict {
+            Verdict::Pass => {passed += 1; format!("PASS     {}_{}", result.day, result.part)}
+            Verdict::Missing => {missing += 1; format!("MISSING  {}_{}: {}", result.day, result.part, result.answer_str())}
+            Verdict::Fail(actual) => {
+                failed += 1;
+                format!("FAIL     {}_{}: expected `{}`, got `{}`", result.day, result.part,
+                        answers.get(result.day, result.part).unwrap(), actual)
+            }
+        };
+        report.push_str(line.as_str());
+        report.push('\n');
+    }
+    for (day, part) in unknown.iter() {
+        report.push_str(format!("UNKNOWN  {}_{}: no such puzzle, expected `{}`\n", day, part, answers.get(*day, *part).unwrap()).as_str());
+    }
+    report.push_str(format!("{} passed, {} failed, {} missing, {} unknown\n", passed, failed, missing, unknown.len()).as_str());
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::input::InputFile;
+
+    fn result(day: usize, part: usize, answer: &str) -> RunResult {
+        RunResult {day, part, answer: Ok(answer.to_string()), elapsed: Duration::from_millis(1)}
+    }
+
+    #[test]
+    fn test_answers() {
+        let input = InputFile::from_reader("# comment\n1_1 1477 + 543 == 2020\n\n12_1 441\n".as_bytes()).unwrap();
+        let answers = Answers::from_file(input.filename()).unwrap();
+        assert_eq!(answers.len(), 2);
+        assert!(!answers.is_empty());
+        assert_eq!(answers.get(1, 1), Some("1477 + 543 == 2020"));
+        assert_eq!(answers.get(12, 1), Some("441"));
+        assert_eq!(answers.get(12, 2), None);
+    }
+
+    #[test]
+    fn test_malformed_answers() {
+        for bad in ["12_1\n", "12 441\n", "12_x 441\n"].iter() {
+            let input = InputFile::from_reader(bad.as_bytes()).unwrap();
+            assert!(Answers::from_file(input.filename()).is_err());
+        }
+    }
+
+    #[test]
+    fn test_report() {
+        let input = InputFile::from_reader("12_1 441\n12_2 1000\n".as_bytes()).unwrap();
+        let answers = Answers::from_file(input.filename()).unwrap();
+        let verified: Vec<(RunResult, Verdict)> = vec![result(12, 1, "441"), result(12, 2, "999"), result(13, 1, "5")]
+            .into_iter()
+            .map(|r| {let v = verdict(&r, &answers); (r, v)})
+            .collect();
+        assert_eq!(verified.iter().map(|(_, v)| v.clone()).collect::<Vec<_>>(),
+                   vec![Verdict::Pass, Verdict::Fail("999".to_string()), Verdict::Missing]);
+        assert!(!all_passed(&verified, &[]));
+        assert!(all_passed(&verified[..1], &[]));
+        assert_eq!(verify_report(&verified, &[], &answers), "\
+PASS     12_1
+FAIL     12_2: expected `1000`, got `999`
+MISSING  13_1: 5
+1 passed, 1 failed, 1 missing, 0 unknown
+");
+    }
+
+    #[test]
+    fn test_unknown() {
+        let input = InputFile::from_reader("12_1 441\n26_1 7\n12_3 5\n".as_bytes()).unwrap();
+        let answers = Answers::from_file(input.filename()).unwrap();
+        let unknown = unknown_keys(&PuzzleRegistry::all(), &answers);
+        assert_eq!(unknown, vec![(12, 3), (26, 1)]);
+        let verified = vec![(result(12, 1, "441"), Verdict::Pass)];
+        assert!(all_passed(&verified, &[]));
+        assert!(!all_passed(&verified, &unknown));
+        assert_eq!(verify_report(&verified, &unknown, &answers), "\
+PASS     12_1
+UNKNOWN  12_3: no such puzzle, expected `5`
+UNKNOWN  26_1: no such puzzle, expected `7`
+1 passed, 0 failed, 0 missing, 2 unknown
+");
+    }
+}